@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+// Progress of an unfinished translate run, so `translate --resume` can pick up where it stopped
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Whether the run was translating all keys rather than only missing ones
+    pub overwrite: bool,
+    /// Locales whose target file was fully written
+    #[serde(default)]
+    pub completed_locales: BTreeSet<String>,
+    /// Finished translations not yet known to be in the target file: locale -> path -> text
+    #[serde(default)]
+    pub keys: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Checkpoint {
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let s = fs::read_to_string(path).with_context(|| format!("Reading {:?}", path))?;
+        let cp =
+            serde_json::from_str(&s).with_context(|| format!("Parsing checkpoint {:?}", path))?;
+        Ok(Some(cp))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_string(self)?)?;
+        fs::rename(&tmp_path, path).with_context(|| format!("Writing {:?}", path))?;
+        Ok(())
+    }
+
+    pub fn remove(path: &Path) -> Result<()> {
+        if path.exists() {
+            fs::remove_file(path).with_context(|| format!("Removing {:?}", path))?;
+        }
+        Ok(())
+    }
+
+    pub fn record(&mut self, locale: &str, path: &str, text: &str) {
+        self.keys
+            .entry(locale.to_string())
+            .or_default()
+            .insert(path.to_string(), text.to_string());
+    }
+
+    pub fn complete_locale(&mut self, locale: &str) {
+        self.keys.remove(locale);
+        self.completed_locales.insert(locale.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn round_trips_and_completing_a_locale_drops_its_keys() {
+        let path = temp_dir("checkpoint").join(".rustylang-checkpoint.json");
+        assert!(Checkpoint::load(&path).unwrap().is_none());
+        let mut cp = Checkpoint {
+            overwrite: true,
+            ..Checkpoint::default()
+        };
+        cp.record("fr-FR", "home.title", "Bienvenue");
+        cp.record("de-DE", "home.title", "Willkommen");
+        cp.complete_locale("de-DE");
+        cp.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path).unwrap().unwrap();
+        assert!(loaded.overwrite);
+        assert_eq!(loaded.keys["fr-FR"]["home.title"], "Bienvenue");
+        assert!(!loaded.keys.contains_key("de-DE") && loaded.completed_locales.contains("de-DE"));
+        fs::write(&path, "{not json").unwrap();
+        assert!(
+            Checkpoint::load(&path)
+                .unwrap_err()
+                .to_string()
+                .contains("Parsing checkpoint")
+        );
+        Checkpoint::remove(&path).unwrap();
+        assert!(!path.exists());
+    }
+}
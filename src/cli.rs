@@ -1,36 +1,124 @@
-use crate::config::load_config;
-use crate::diff::{compute_missing_translations, flatten_string_paths};
-use crate::json_utils::{read_json_file, set_value_at_path, write_json_atomic};
-use crate::openai_client::OpenAiTranslator;
-use anyhow::{anyhow, Context, Result};
+use crate::checkpoint::Checkpoint;
+use crate::config::Config;
+use crate::config::{
+    CONFIG_FILE, FailurePolicy, FallbackMode, ResponseFormat, SortKeys, load_config,
+};
+use crate::diff::{changed_keys, keys_to_translate, prune_orphans};
+use crate::estimate::{TokenEstimate, estimate_request, price_for};
+use crate::examples::ExamplePool;
+use crate::git::read_file_at_ref;
+use crate::init::{
+    count_strings, detect_format, guess_source_locale, infer_layouts, render_config,
+    scan_json_files,
+};
+use crate::json_utils::{
+    apply_key_order, read_json_file, render_json, set_value_at_path, write_json_atomic,
+};
+use crate::locales::{
+    LocaleFile, discover_locales, discover_namespaces, flatten_locale, is_locale_code,
+    is_namespaced, locale_path, namespace_path, read_locale,
+};
+use crate::openai_client::{Budget, OpenAiTranslator, RetryPolicy, Sampling, TokenUsageSnapshot};
+use crate::patterns::{KeyFilter, KeyMatcher};
+use crate::prompt::{PromptVars, Prompts, with_output_rule};
+use crate::report::{LocaleReport, RunReport};
+use crate::secrets;
+use crate::state::TranslationState;
+use crate::stats::{compute_stats, render_markdown, render_table};
+use crate::translate::{
+    LocaleOutcome, TranslateRun, fallback_values, fallback_waves, required_tokens,
+};
+use anyhow::{Context, Result, anyhow};
 use clap::{Args, Parser, Subcommand};
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use futures::{stream, StreamExt};
+use futures::{StreamExt, stream};
 use serde_json::Value;
-use std::env;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::path::PathBuf;
-use tracing::{error, info};
-use regex::Regex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
 
 #[derive(Parser, Debug)]
-#[command(name = "rustylang", version, about = "i18n helper CLI")] 
+#[command(name = "rustylang", version, about = "i18n helper CLI")]
 pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
     #[command(subcommand)]
     pub command: Commands,
 }
 
+#[derive(Args, Debug)]
+pub struct GlobalArgs {
+    /// Config file to use (default: nearest rustylang.toml up to the repository root)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// Directory that file_pattern, state_file and checkpoint_file are relative to
+    /// (default: the config file's directory)
+    #[arg(long, global = true)]
+    pub root: Option<PathBuf>,
+    /// Only work on this `[[project]]` (default: all of them)
+    #[arg(long, global = true)]
+    pub project: Option<String>,
+    /// Override any config value, e.g. --set openai.max_retries=3 (repeatable; wins over
+    /// RUSTYLANG_* variables)
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    pub sets: Vec<(String, String)>,
+}
+
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got {:?}", s))?;
+    Ok((key.trim().to_string(), value.to_string()))
+}
+
+impl GlobalArgs {
+    // Effective config of every selected project (just one without [[project]] tables)
+    fn load_projects(&self) -> Result<Vec<Config>> {
+        load_config(self.config.as_deref(), self.root.as_deref(), &self.sets)?
+            .projects(self.project.as_deref())
+    }
+
+    // For commands that work on a single project: --project is needed when several are configured
+    fn load_config(&self) -> Result<Config> {
+        let mut projects = self.load_projects()?;
+        if projects.len() > 1 {
+            let names: Vec<String> = projects.iter().filter_map(|p| p.project.clone()).collect();
+            return Err(anyhow!(
+                "Several projects configured ({}); pick one with --project",
+                names.join(", ")
+            ));
+        }
+        Ok(projects.remove(0))
+    }
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Set/update a string in the source locale file (default en-GB.json)
     Set(SetArgs),
     /// Translate missing (or all with --overwrite) strings for configured locales
     Translate(TranslateArgs),
+    /// Rewrite every locale file with the configured formatting and key order
+    Fmt(FmtArgs),
+    /// Remove keys from target locales that no longer exist in the source
+    Prune(PruneArgs),
+    /// Show translation coverage per locale
+    Stats(StatsArgs),
+    /// Inspect the configuration
+    Config(ConfigArgs),
+    /// Inspect the prompts sent to the model
+    Prompt(PromptArgs),
+    /// Create rustylang.toml from the locale files found in this directory
+    Init(InitArgs),
 }
 
 #[derive(Args, Debug)]
 pub struct SetArgs {
-    /// Dot path (supports escaping with \\.) e.g. flows.general-title
+    /// Dot path (supports escaping with \\.) e.g. flows.general-title; `namespace:path` with
+    /// `{namespace}` patterns
     pub path: String,
     /// Text to set (string)
     pub text: String,
@@ -56,16 +144,143 @@ pub struct TranslateArgs {
     /// Dry run: show planned changes only
     #[arg(long)]
     pub dry_run: bool,
-    /// Model override (defaults from config)
+    /// Model override for every locale (defaults from config)
     #[arg(long)]
     pub model: Option<String>,
+    /// Estimate tokens and cost for the run without calling the API
+    #[arg(long)]
+    pub estimate: bool,
+    /// Stop sending requests once this many tokens have been used (overrides config)
+    #[arg(long)]
+    pub max_tokens_total: Option<u64>,
+    /// Stop sending requests once this much USD has been spent (overrides config)
+    #[arg(long)]
+    pub max_cost: Option<f64>,
+    /// Continue an interrupted run from the checkpoint file
+    #[arg(long)]
+    pub resume: bool,
+    /// What to do when a key fails to translate (overrides config)
+    #[arg(long, value_enum)]
+    pub on_failure: Option<FailurePolicy>,
+    /// Write a JSON report of the run (per-locale results, retries, latency, usage) to this file
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+    /// Only translate keys added or changed in the source file since this git ref
+    #[arg(long)]
+    pub since: Option<String>,
+    /// Only translate keys matching this pattern (repeatable; replaces config include)
+    #[arg(long = "only")]
+    pub only: Vec<String>,
+    /// Skip keys matching this pattern (repeatable; adds to config exclude)
+    #[arg(long)]
+    pub exclude: Vec<String>,
 }
 
-pub async fn handle_set(args: SetArgs) -> Result<()> {
-    let cfg = load_config()?;
-    let file = args.file.unwrap_or_else(|| {
-        PathBuf::from(cfg.file_pattern.replace("{locale}", &cfg.source_locale))
-    });
+#[derive(Args, Debug)]
+pub struct FmtArgs {
+    /// Check only: exit non-zero if any file would be reformatted
+    #[arg(long)]
+    pub check: bool,
+    /// Indent width override (defaults from config)
+    #[arg(long)]
+    pub indent: Option<usize>,
+    /// Key order override (defaults from config)
+    #[arg(long, value_enum)]
+    pub sort_keys: Option<SortKeys>,
+}
+
+#[derive(Args, Debug)]
+pub struct PruneArgs {
+    /// Comma-separated locales to prune (defaults to config, then all locale files found)
+    #[arg(long)]
+    pub locales: Option<String>,
+    /// Dry run: show keys that would be removed only
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Extra key pattern to never remove (repeatable; adds to config prune_keep)
+    #[arg(long)]
+    pub keep: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    /// Comma-separated locales to report (defaults to config, then all locale files found)
+    #[arg(long)]
+    pub locales: Option<String>,
+    /// Output format
+    #[arg(long, value_enum, default_value = "table")]
+    pub format: StatsFormat,
+}
+
+#[derive(Args, Debug)]
+pub struct InitArgs {
+    /// Accept the detected settings without prompting
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+    /// Create empty files for target locales that don't have one yet
+    #[arg(long)]
+    pub create_missing: bool,
+    /// Overwrite an existing config file
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the effective config (after projects, RUSTYLANG_* and --set) and where each value
+    /// came from
+    Show,
+}
+
+#[derive(Args, Debug)]
+pub struct PromptArgs {
+    #[command(subcommand)]
+    pub command: PromptCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PromptCommand {
+    /// Print the system prompt and message `translate` would send for a source key
+    Preview {
+        /// Dot path of the source key; `namespace:path` with `{namespace}` patterns
+        key: String,
+        /// Target locale
+        #[arg(long)]
+        locale: String,
+    },
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum StatsFormat {
+    Table,
+    Json,
+    Markdown,
+}
+
+pub async fn handle_set(args: SetArgs, global: &GlobalArgs) -> Result<()> {
+    let cfg = global.load_config()?;
+    let (file, path) = match args.file {
+        Some(file) => (file, args.path.as_str()),
+        None if is_namespaced(&cfg.file_pattern) => {
+            let (ns, path) = args.path.split_once(':').ok_or_else(|| {
+                anyhow!("Key must be namespace-qualified, e.g. common:{}", args.path)
+            })?;
+            (
+                namespace_path(&cfg.file_pattern, &cfg.source_locale, Some(ns)),
+                path,
+            )
+        }
+        None => (
+            locale_path(&cfg.file_pattern, &cfg.source_locale),
+            args.path.as_str(),
+        ),
+    };
 
     // Read file
     let mut json = read_json_file(&file).with_context(|| format!("Reading {:?}", file))?;
@@ -73,122 +288,300 @@ pub async fn handle_set(args: SetArgs) -> Result<()> {
     // Update
     // Create intermediate objects by default for better UX
     let create_missing = !args.no_create_missing;
-    set_value_at_path(&mut json, &args.path, Value::String(args.text.clone()), create_missing)
-        .with_context(|| format!("Setting {} in {:?}", args.path, file))?;
+    set_value_at_path(
+        &mut json,
+        path,
+        Value::String(args.text.clone()),
+        create_missing,
+    )
+    .with_context(|| format!("Setting {} in {:?}", args.path, file))?;
 
     // Write atomically
-    write_json_atomic(&file, &json).with_context(|| format!("Writing {:?}", file))?;
+    apply_key_order(&mut json, cfg.format.sort_keys, None);
+    write_json_atomic(&file, &json, &cfg.format).with_context(|| format!("Writing {:?}", file))?;
 
     info!(path=?args.path, file=?file, "Updated translation");
     Ok(())
 }
 
-pub async fn handle_translate(args: TranslateArgs) -> Result<()> {
-    let mut cfg = load_config()?;
-    if let Some(c) = args.concurrency { cfg.concurrency = c; }
-    if let Some(m) = args.model.clone() { cfg.openai.model = m; }
+// One project's share of a translate run, worked out before any request is made
+struct ProjectPlan {
+    cfg: Config,
+    locales: Vec<String>,
+    sources: Vec<LocaleFile>,
+    changed_keys: Option<BTreeSet<String>>,
+}
+
+// Command-line settings win over both the top level and `[[project]]` tables
+fn apply_translate_overrides(cfg: &mut Config, args: &TranslateArgs) {
+    if let Some(c) = args.concurrency {
+        cfg.concurrency = c;
+    }
+    if let Some(m) = args.model.clone() {
+        cfg.openai.model = m;
+        for settings in cfg.locales.settings_mut() {
+            settings.model = None;
+        }
+    }
+    if let Some(t) = args.max_tokens_total {
+        cfg.openai.budget.max_tokens = Some(t);
+    }
+    if let Some(c) = args.max_cost {
+        cfg.openai.budget.max_cost = Some(c);
+    }
+    if let Some(p) = args.on_failure {
+        cfg.translate.on_failure = p;
+    }
+    if !args.only.is_empty() {
+        cfg.translate.include = args.only.clone();
+    }
+    cfg.translate.exclude.extend(args.exclude.iter().cloned());
+}
 
+// None when `--since` leaves nothing to do for the project
+fn plan_project(cfg: Config, args: &TranslateArgs) -> Result<Option<ProjectPlan>> {
+    let label = cfg
+        .project
+        .as_ref()
+        .map(|p| format!(" [{}]", p))
+        .unwrap_or_default();
     let locales: Vec<String> = match args.locales.as_ref() {
-        Some(s) => s.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect(),
-        None => cfg.locales.clone(),
+        Some(s) => split_locales(s),
+        None => cfg.locales.names().to_vec(),
     };
     if locales.is_empty() {
-        return Err(anyhow!("No locales specified (config or --locales)"));
+        return Err(anyhow!(
+            "No locales specified (config or --locales){}",
+            label
+        ));
     }
+    if let Some(bad) = locales.iter().find(|l| !is_locale_code(l)) {
+        return Err(anyhow!(
+            "{:?} is not a BCP 47 language tag (e.g. en-GB, pt-BR){}",
+            bad,
+            label
+        ));
+    }
+    cfg.validate()?;
+
+    let sources = read_source(&cfg)?;
+    let changed_keys = match &args.since {
+        Some(git_ref) => {
+            let mut old = Vec::with_capacity(sources.len());
+            for file in &sources {
+                let json = match read_file_at_ref(git_ref, &file.path)? {
+                    Some(s) => serde_json::from_str(&s)
+                        .with_context(|| format!("Parsing {:?} at {}", file.path, git_ref))?,
+                    None => Value::Object(serde_json::Map::new()),
+                };
+                old.push(LocaleFile {
+                    json,
+                    ..file.clone()
+                });
+            }
+            let keys = changed_keys(&flatten_locale(&old), &flatten_locale(&sources));
+            info!(
+                project = ?cfg.project, since = %git_ref, count = %keys.len(),
+                "Restricting to keys changed in source"
+            );
+            if keys.is_empty() {
+                println!("No source keys changed since {}{}", git_ref, label);
+                return Ok(None);
+            }
+            Some(keys)
+        }
+        None => None,
+    };
+    Ok(Some(ProjectPlan {
+        cfg,
+        locales,
+        sources,
+        changed_keys,
+    }))
+}
 
-    let source_file = PathBuf::from(cfg.file_pattern.replace("{locale}", &cfg.source_locale));
-    let source = read_json_file(&source_file)
-        .with_context(|| format!("Reading source file {:?}", source_file))?;
-    let source_flat = flatten_string_paths(&source, None);
-    if source_flat.is_empty() {
-        return Err(anyhow!("No string leaves found in source {:?}", source_file));
+pub async fn handle_translate(args: TranslateArgs, global: &GlobalArgs) -> Result<()> {
+    // openai settings and concurrency of the shared translator come from the top level
+    let mut cfg = load_config(
+        global.config.as_deref(),
+        global.root.as_deref(),
+        &global.sets,
+    )?;
+    apply_translate_overrides(&mut cfg, &args);
+    let mut plans = Vec::new();
+    for mut project in cfg.projects(global.project.as_deref())? {
+        apply_translate_overrides(&mut project, &args);
+        plans.extend(plan_project(project, &args)?);
+    }
+    if plans.is_empty() {
+        return Ok(());
     }
 
-    // Translator setup
-    let api_key = env::var("OPENAI_API_KEY")
-        .ok()
-        .or(cfg.openai.api_key.clone())
-        .unwrap_or_default();
-    if api_key.is_empty() {
-        return Err(anyhow!("OPENAI_API_KEY not set and no key in config"));
-    }
-    let translator = OpenAiTranslator::new(api_key, cfg.openai.model.clone(), cfg.concurrency)?;
-
-    let mp = MultiProgress::new();
-    let pb_style = ProgressStyle::with_template("{msg} {bar:40.cyan/blue} {pos}/{len}")
-        .unwrap()
-        .progress_chars("##-");
-
-    // Process locales concurrently (bounded by cfg.concurrency)
-    let mp = Arc::new(mp);
-    let translator = translator.clone();
-    let file_pattern = cfg.file_pattern.clone();
-    let source_locale = cfg.source_locale.clone();
-    let concurrency = cfg.concurrency;
-    let results = stream::iter(locales.into_iter())
-        .map(|locale| {
-            let translator = translator.clone();
-            let mp = mp.clone();
-            let pb_style = pb_style.clone();
-            let source = source.clone();
-            let file_pattern = file_pattern.clone();
-            let source_locale = source_locale.clone();
-            async move {
-                if locale == source_locale { return Ok::<(), anyhow::Error>(()); }
-                let target_file = PathBuf::from(file_pattern.replace("{locale}", &locale));
-                let mut target = read_json_file(&target_file).unwrap_or(Value::Object(serde_json::Map::new()));
-                let to_fill = compute_missing_translations(&source, &target, args.overwrite);
-                if to_fill.is_empty() {
-                    info!(locale=%locale, "No translations needed");
-                    return Ok(());
-                }
+    if args.estimate {
+        for plan in &plans {
+            if let Some(name) = &plan.cfg.project {
+                println!("Project {}:", name);
+            }
+            estimate_translate(
+                &plan.cfg,
+                &plan.locales,
+                &plan.sources,
+                args.overwrite,
+                plan.changed_keys.as_ref(),
+            )?;
+        }
+        return Ok(());
+    }
 
-                let pb = mp.add(ProgressBar::new(to_fill.len() as u64));
-                pb.set_style(pb_style.clone());
-                pb.set_message(format!("{}", locale));
-
-                let updates = stream::iter(to_fill.into_iter())
-                    .map(|(path, english)| {
-                        let translator = translator.clone();
-                        let source_locale = source_locale.clone();
-                        let locale = locale.clone();
-                        async move {
-                            if args.dry_run {
-                                return Ok::<(String, String), anyhow::Error>((path, String::from("<translated>")));
-                            }
-                            let placeholders = extract_placeholders(&english);
-                            match translator.translate(Some(&path), &english, &source_locale, &locale, &placeholders).await {
-                                Ok(tx) => Ok((path, tx)),
-                                Err(err) => {
-                                    error!(?err, path=%path, "Translation failed, using source text");
-                                    Ok((path, english))
-                                }
-                            }
-                        }
-                    })
-                    .buffer_unordered(concurrency)
-                    .inspect(|_| pb.inc(1))
-                    .collect::<Vec<_>>()
-                    .await;
+    // Translator setup
+    let api_key = secrets::api_key(&cfg)?;
+    let mut translator = OpenAiTranslator::new(api_key, &cfg.openai, cfg.concurrency)?
+        .with_retry(RetryPolicy::from_config(&cfg.openai))
+        .with_sampling(Sampling::from_config(&cfg.openai))
+        .with_response_format(cfg.openai.response_format)
+        .with_rate_limits(cfg.openai.requests_per_minute, cfg.openai.tokens_per_minute);
+    let limits = &cfg.openai.budget;
+    if limits.max_tokens.is_some() || limits.max_cost.is_some() {
+        let prices = cfg.openai.prices.clone();
+        if limits.max_cost.is_some() {
+            let models = plans.iter().flat_map(|plan| {
+                plan.locales
+                    .iter()
+                    .map(|l| locale_model(&plan.cfg, l).unwrap_or(&cfg.openai.model))
+            });
+            if let Some(model) = std::iter::once(&cfg.openai.model)
+                .chain(models)
+                .find(|m| price_for(m, &prices).is_none())
+            {
+                return Err(anyhow!(
+                    "max_cost set but no price known for {}; add it under [openai.prices]",
+                    model
+                ));
+            }
+        }
+        translator = translator.with_budget(Budget {
+            max_tokens: limits.max_tokens,
+            max_cost: limits.max_cost,
+            prices,
+        });
+    }
+    let translator = Arc::new(translator);
 
-                pb.finish_and_clear();
-                if args.dry_run { info!(locale=%locale, count=%updates.len(), "Dry run: would update keys"); return Ok(()); }
+    // Shared by every project's run so Ctrl-C and `on_failure = "abort"` stop all of them
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let aborted = Arc::new(AtomicBool::new(false));
+    let mut runs = Vec::with_capacity(plans.len());
+    let mut resumable = false;
+    for plan in plans {
+        let checkpoint_file = PathBuf::from(&plan.cfg.checkpoint_file);
+        // only read with --resume, so a corrupt or stale checkpoint cannot block a normal run
+        let checkpoint = if args.resume {
+            let checkpoint = Checkpoint::load(&checkpoint_file)?;
+            if let Some(cp) = &checkpoint {
+                info!(
+                    file = ?checkpoint_file, completed = %cp.completed_locales.len(),
+                    "Resuming from checkpoint"
+                );
+                resumable = true;
+            }
+            checkpoint
+        } else {
+            if checkpoint_file.exists() {
+                warn!(
+                    file = ?checkpoint_file,
+                    "Ignoring checkpoint from an unfinished run (use --resume to continue it)"
+                );
+            }
+            None
+        };
+        // a resumed run keeps the mode it was started with
+        let overwrite = args.overwrite || checkpoint.as_ref().is_some_and(|cp| cp.overwrite);
+        let mut run = TranslateRun::new(
+            &plan.cfg,
+            translator.clone(),
+            plan.sources,
+            overwrite,
+            args.dry_run,
+        )?;
+        run.changed_keys = plan.changed_keys;
+        run.interrupted = interrupted.clone();
+        run.aborted = aborted.clone();
+        if let Some(cp) = checkpoint {
+            *run.checkpoint.lock().unwrap() = cp;
+        }
+        runs.push((plan.cfg, plan.locales, run));
+    }
+    if args.resume && !resumable {
+        let files: Vec<&str> = runs
+            .iter()
+            .map(|(cfg, _, _)| cfg.checkpoint_file.as_str())
+            .collect();
+        return Err(anyhow!("No checkpoint found at {}", files.join(", ")));
+    }
+    let started_at = SystemTime::now();
 
-                for item in updates.into_iter() {
-                    let (path, txt) = item?;
-                    set_value_at_path(&mut target, &path, Value::String(txt), true)?;
-                }
-                write_json_atomic(&target_file, &target)?;
-                info!(locale=%locale, file=?target_file, "Wrote translations");
-                Ok(())
+    // First Ctrl-C stops queuing new requests and saves progress; a second one aborts
+    let signal_flag = interrupted.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            signal_flag.store(true, Ordering::Relaxed);
+            warn!(
+                "Interrupted: finishing in-flight requests and saving progress \
+                 (Ctrl-C again to abort)"
+            );
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(130);
             }
-        })
-        .buffer_unordered(concurrency)
-        .collect::<Vec<_>>()
-        .await;
+        }
+    });
 
-    for res in results { res?; }
+    let mut outcomes = Vec::new();
+    // Locales that could not be processed at all: (project, locale, error)
+    let mut locale_errors = Vec::new();
+    for (project_cfg, locales, run) in &runs {
+        // Process locales concurrently (bounded by concurrency), fallback parents first
+        let mut results = Vec::new();
+        for wave in fallback_waves(locales, &project_cfg.locales) {
+            results.extend(
+                stream::iter(wave)
+                    .map(|locale| async move { (locale.clone(), run.run_locale(locale).await) })
+                    .buffer_unordered(project_cfg.concurrency)
+                    .collect::<Vec<_>>()
+                    .await,
+            );
+        }
 
+        let mut run_outcomes = Vec::new();
+        let mut run_failed = false;
+        for (locale, res) in results {
+            match res {
+                Ok(outcome) if outcome.locale == project_cfg.source_locale => {}
+                Ok(outcome) => run_outcomes.push(outcome),
+                Err(err) => {
+                    run_failed = true;
+                    locale_errors.push((project_cfg.project.clone(), locale, err));
+                }
+            }
+        }
+        if !args.dry_run {
+            run.state
+                .lock()
+                .unwrap()
+                .save(Path::new(&project_cfg.state_file))?;
+            let checkpoint = run.checkpoint.lock().unwrap();
+            if run_outcomes.iter().all(|o| o.skipped.is_empty()) && !run_failed {
+                Checkpoint::remove(&run.checkpoint_file)?;
+            } else {
+                checkpoint.save(&run.checkpoint_file)?;
+            }
+        }
+        outcomes.extend(run_outcomes);
+    }
+    outcomes.sort_by(|a, b| (&a.project, &a.locale).cmp(&(&b.project, &b.locale)));
+    let skipped_count: usize = outcomes.iter().map(|o| o.skipped.len()).sum();
+    let failed_count: usize = outcomes.iter().map(|o| o.failed.len()).sum();
+    let interrupted = interrupted.load(Ordering::Relaxed);
+    let aborted = aborted.load(Ordering::Relaxed);
     // Token usage summary
     let usage = translator.usage_snapshot();
     info!(
@@ -218,25 +611,602 @@ pub async fn handle_translate(args: TranslateArgs) -> Result<()> {
         }
     }
 
+    if skipped_count > 0 {
+        let reason = if interrupted {
+            "Interrupted"
+        } else if aborted {
+            "Aborted"
+        } else {
+            "Budget reached"
+        };
+        println!(
+            "{}: skipped {} key(s) (left untranslated):",
+            reason, skipped_count
+        );
+        for o in &outcomes {
+            for key in &o.skipped {
+                println!("  {}: {}", o.label(), key);
+            }
+        }
+    }
+    // grouped per project, as each may set its own on_failure
+    for (project_cfg, _, _) in &runs {
+        let failed: Vec<&LocaleOutcome> = outcomes
+            .iter()
+            .filter(|o| o.project == project_cfg.project && !o.failed.is_empty())
+            .collect();
+        if failed.is_empty() {
+            continue;
+        }
+        let count: usize = failed.iter().map(|o| o.failed.len()).sum();
+        let label = project_cfg
+            .project
+            .as_ref()
+            .map(|p| format!(" in {}", p))
+            .unwrap_or_default();
+        println!(
+            "Failed: {} key(s){} (on_failure = {:?}):",
+            count, label, project_cfg.translate.on_failure
+        );
+        for o in failed {
+            for (key, err) in &o.failed {
+                println!("  {}: {}: {}", o.label(), key, err);
+            }
+        }
+    }
+
+    if let Some(path) = &args.report {
+        let per_locale: HashMap<String, TokenUsageSnapshot> =
+            translator.usage_by_locale_snapshot().into_iter().collect();
+        let usage_of = |o: &LocaleOutcome| per_locale.get(&o.label()).copied().unwrap_or_default();
+        let mut locales: Vec<LocaleReport> = outcomes
+            .iter()
+            .map(|o| LocaleReport::new(o, usage_of(o)))
+            .collect();
+        for (project, locale, err) in &locale_errors {
+            let outcome = LocaleOutcome {
+                project: project.clone(),
+                locale: locale.clone(),
+                ..LocaleOutcome::default()
+            };
+            locales.push(LocaleReport {
+                error: Some(format!("{:#}", err)),
+                ..LocaleReport::new(&outcome, usage_of(&outcome))
+            });
+        }
+        locales.sort_by(|a, b| (&a.project, &a.locale).cmp(&(&b.project, &b.locale)));
+        let report = RunReport {
+            started_at: started_at
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            duration_ms: started_at
+                .elapsed()
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            model: cfg.openai.model.clone(),
+            source_locale: cfg.source_locale.clone(),
+            dry_run: args.dry_run,
+            interrupted,
+            aborted,
+            usage,
+            locales,
+        };
+        report.write(path)?;
+        info!(file=?path, "Wrote run report");
+    }
+
+    // after the report, so runs where a locale failed outright still produce one
+    if let Some((_, _, err)) = locale_errors.into_iter().next() {
+        return Err(err);
+    }
+    if interrupted {
+        return Err(anyhow!(
+            "Run interrupted; continue with `rustylang translate --resume`"
+        ));
+    }
+    if failed_count > 0 {
+        return Err(anyhow!("{} key(s) failed to translate", failed_count));
+    }
+    Ok(())
+}
+
+// Offline estimate of the prompts `translate` would send, priced from config/built-in tables
+fn estimate_translate(
+    cfg: &Config,
+    locales: &[String],
+    sources: &[LocaleFile],
+    overwrite: bool,
+    changed: Option<&BTreeSet<String>>,
+) -> Result<()> {
+    println!(
+        "Estimate for model {} (approximate token counts, no API calls made):",
+        cfg.openai.model
+    );
+
+    let filter = KeyFilter::new(&cfg.translate.include, &cfg.translate.exclude)?;
+    let verbatim = KeyMatcher::new(&cfg.translate.verbatim)?;
+    let prompts = Prompts::from_config(cfg)?;
+    let state = TranslationState::load(Path::new(&cfg.state_file))?;
+    let source_flat = flatten_locale(sources);
+    let namespaces: Vec<Option<String>> = sources.iter().map(|f| f.namespace.clone()).collect();
+    let json = cfg.openai.response_format == ResponseFormat::Json;
+    let mut total = TokenEstimate::default();
+    let mut total_cost = Some(0.0);
+    for locale in locales.iter().filter(|l| **l != cfg.source_locale) {
+        let settings = cfg
+            .locales
+            .settings()
+            .get(locale)
+            .cloned()
+            .unwrap_or_default();
+        let model = locale_model(cfg, locale).unwrap_or(&cfg.openai.model);
+        let price = price_for(model, &cfg.openai.prices);
+        let target = flatten_locale(&read_locale(&cfg.file_pattern, locale, &namespaces)?);
+        let mut to_fill = keys_to_translate(&source_flat, &target, overwrite, changed);
+        to_fill.retain(|(path, _)| filter.allows(path) && !verbatim.is_match(path));
+        // parent values as currently on disk; copied keys cost nothing
+        let parents = fallback_values(
+            &cfg.file_pattern,
+            &namespaces,
+            &cfg.locales.fallback_chain(locale),
+        )?;
+        if settings.fallback_mode.unwrap_or(FallbackMode::Copy) == FallbackMode::Copy {
+            to_fill.retain(|(path, _)| !parents.contains_key(path));
+        }
+        let pending: HashSet<&str> = to_fill.iter().map(|(path, _)| path.as_str()).collect();
+        let pool = ExamplePool::new(&source_flat, &target, |path| {
+            pending.contains(path) || state.is_untranslated(locale, path)
+        });
+        let examples = settings.examples.unwrap_or(cfg.translate.examples);
+        let mut est = TokenEstimate::default();
+        for (path, english) in &to_fill {
+            let examples = pool.most_similar(path, english, examples);
+            let required = required_tokens(english, &cfg.translate.protected_terms);
+            let vars = PromptVars {
+                key: Some(path),
+                source_locale: &cfg.source_locale,
+                target_locale: locale,
+                text: english,
+                required: &required,
+                settings: &settings,
+                examples: &examples,
+            };
+            let (system, text) = prompts.request(vars, parents.get(path));
+            est.add(estimate_request(&with_output_rule(&system, json), &text));
+        }
+        total.add(est);
+        total_cost = total_cost.zip(price).map(|(sum, p)| sum + est.cost(&p));
+        println!(
+            "  {}{}: keys={}, prompt≈{}, completion≈{}, total≈{}{}",
+            locale,
+            if *model == cfg.openai.model {
+                String::new()
+            } else {
+                format!(" ({})", model)
+            },
+            to_fill.len(),
+            est.prompt_tokens,
+            est.completion_tokens,
+            est.total(),
+            price
+                .map(|p| format!(", cost≈${:.6}", est.cost(&p)))
+                .unwrap_or_default()
+        );
+    }
+
+    println!(
+        "Total: prompt≈{}, completion≈{}, total≈{}",
+        total.prompt_tokens,
+        total.completion_tokens,
+        total.total()
+    );
+    match total_cost {
+        Some(cost) => println!("Estimated cost: ${:.6}", cost),
+        None => println!(
+            "No price known for some models; add them under [openai.prices] to estimate cost"
+        ),
+    }
+    Ok(())
+}
+
+// The `[locales."xx"]` model override, if any
+fn locale_model<'a>(cfg: &'a Config, locale: &str) -> Option<&'a String> {
+    cfg.locales
+        .settings()
+        .get(locale)
+        .and_then(|s| s.model.as_ref())
+}
+
+pub async fn handle_prune(args: PruneArgs, global: &GlobalArgs) -> Result<()> {
+    let mut total = 0;
+    for mut cfg in global.load_projects()? {
+        cfg.translate.prune_keep.extend(args.keep.iter().cloned());
+        let keep = KeyMatcher::new(&cfg.translate.prune_keep)?;
+        let locales = target_locales(args.locales.as_deref(), &cfg)?;
+
+        // read_source refuses an empty source, which would prune every target key
+        let sources = read_source(&cfg)?;
+        let namespaces: Vec<Option<String>> = sources.iter().map(|f| f.namespace.clone()).collect();
+
+        for locale in &locales {
+            for (target, source) in read_locale(&cfg.file_pattern, locale, &namespaces)?
+                .into_iter()
+                .zip(&sources)
+            {
+                if !target.path.exists() {
+                    continue;
+                }
+                let mut json = target.json;
+                let removed =
+                    prune_orphans(&mut json, &source.json, source.namespace.as_deref(), &keep);
+                if removed.is_empty() {
+                    continue;
+                }
+
+                println!("{}:", target.path.display());
+                for (path, value) in &removed {
+                    println!("  - {}: {}", path, value);
+                }
+                total += removed.len();
+                if !args.dry_run {
+                    write_formatted(&target.path, json, &source.json, &cfg)?;
+                }
+            }
+        }
+    }
+
+    if args.dry_run {
+        println!("Dry run: would remove {} orphaned key(s)", total);
+    } else {
+        println!("Removed {} orphaned key(s)", total);
+    }
+    Ok(())
+}
+
+fn write_formatted(file: &Path, mut json: Value, source: &Value, cfg: &Config) -> Result<()> {
+    apply_key_order(&mut json, cfg.format.sort_keys, Some(source));
+    write_json_atomic(file, &json, &cfg.format).with_context(|| format!("Writing {:?}", file))
+}
+
+pub async fn handle_stats(args: StatsArgs, global: &GlobalArgs) -> Result<()> {
+    let mut stats = Vec::new();
+    for cfg in global.load_projects()? {
+        let locales = target_locales(args.locales.as_deref(), &cfg)?;
+        let sources = read_source(&cfg)?;
+        let source_flat = flatten_locale(&sources);
+        let namespaces: Vec<Option<String>> = sources.iter().map(|f| f.namespace.clone()).collect();
+        let state = TranslationState::load(Path::new(&cfg.state_file))?;
+
+        for locale in &locales {
+            let target_flat = flatten_locale(&read_locale(&cfg.file_pattern, locale, &namespaces)?);
+            let mut row = compute_stats(locale, &source_flat, &target_flat, &state);
+            if let Some(project) = &cfg.project {
+                row.locale = format!("{}/{}", project, locale);
+            }
+            stats.push(row);
+        }
+    }
+
+    match args.format {
+        StatsFormat::Table => print!("{}", render_table(&stats)),
+        StatsFormat::Json => println!("{}", serde_json::to_string_pretty(&stats)?),
+        StatsFormat::Markdown => print!("{}", render_markdown(&stats)),
+    }
+    Ok(())
+}
+
+// Every file of the source locale (one per namespace discovered on disk), refusing an empty source
+fn read_source(cfg: &Config) -> Result<Vec<LocaleFile>> {
+    let namespaces = discover_namespaces(&cfg.file_pattern, &cfg.source_locale)?;
+    if namespaces.is_empty() {
+        return Err(anyhow!(
+            "No namespace files found for source locale {} ({:?})",
+            cfg.source_locale,
+            cfg.file_pattern
+        ));
+    }
+    let sources = read_locale(&cfg.file_pattern, &cfg.source_locale, &namespaces)
+        .context("Reading source locale")?;
+    if flatten_locale(&sources).is_empty() {
+        let files: Vec<_> = sources
+            .iter()
+            .map(|f| f.path.display().to_string())
+            .collect();
+        return Err(anyhow!(
+            "No string leaves found in source {}",
+            files.join(", ")
+        ));
+    }
+    Ok(sources)
+}
+
+// Locales to work on besides the source: --locales, else config, else every locale file on disk
+fn target_locales(arg: Option<&str>, cfg: &Config) -> Result<Vec<String>> {
+    let mut locales = match arg {
+        Some(s) => split_locales(s),
+        None if !cfg.locales.is_empty() => cfg.locales.names().to_vec(),
+        None => discover_locales(&cfg.file_pattern)?,
+    };
+    locales.retain(|l| *l != cfg.source_locale);
+    Ok(locales)
+}
+
+fn split_locales(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+pub async fn handle_fmt(args: FmtArgs, global: &GlobalArgs) -> Result<()> {
+    let mut changed = Vec::new();
+    for mut cfg in global.load_projects()? {
+        if let Some(i) = args.indent {
+            cfg.format.indent = i;
+        }
+        if let Some(o) = args.sort_keys {
+            cfg.format.sort_keys = o;
+        }
+
+        let locales = discover_locales(&cfg.file_pattern)?;
+        if locales.is_empty() {
+            return Err(anyhow!(
+                "No locale files found matching {:?}",
+                cfg.file_pattern
+            ));
+        }
+        let source_namespaces = discover_namespaces(&cfg.file_pattern, &cfg.source_locale)?;
+        let sources = read_locale(&cfg.file_pattern, &cfg.source_locale, &source_namespaces)?;
+
+        for locale in locales {
+            for ns in discover_namespaces(&cfg.file_pattern, &locale)? {
+                let file = namespace_path(&cfg.file_pattern, &locale, ns.as_deref());
+                let current = std::fs::read_to_string(&file)
+                    .with_context(|| format!("Reading {:?}", file))?;
+                let mut json = read_json_file(&file)?;
+                let reference = sources
+                    .iter()
+                    .find(|s| s.namespace == ns && locale != cfg.source_locale)
+                    .map(|s| &s.json);
+                apply_key_order(&mut json, cfg.format.sort_keys, reference);
+                if render_json(&json, &cfg.format)? == current {
+                    continue;
+                }
+                if !args.check {
+                    write_json_atomic(&file, &json, &cfg.format)
+                        .with_context(|| format!("Writing {:?}", file))?;
+                    info!(file=?file, "Formatted");
+                }
+                changed.push(file);
+            }
+        }
+    }
+
+    if args.check {
+        for file in &changed {
+            println!("Would reformat {}", file.display());
+        }
+        if !changed.is_empty() {
+            return Err(anyhow!("{} file(s) need formatting", changed.len()));
+        }
+    } else {
+        println!("Formatted {} file(s)", changed.len());
+    }
     Ok(())
 }
 
-fn extract_placeholders(s: &str) -> Vec<String> {
-    let mut out = Vec::new();
-    // Patterns: {word}, {{mustache}}, :named, %s, %d, {0}, {name}
-    let patterns = vec![
-        Regex::new(r"\{\{[^}]+\}\}").unwrap(),
-        Regex::new(r"\{[^}]+\}").unwrap(),
-        Regex::new(r":[A-Za-z_][A-Za-z0-9_]*").unwrap(),
-        Regex::new(r"%[sd]?").unwrap(),
-    ];
-    for re in patterns.iter() {
-        for m in re.find_iter(s) {
-            let p = m.as_str().to_string();
-            if !out.contains(&p) { out.push(p); }
+pub async fn handle_config(args: ConfigArgs, global: &GlobalArgs) -> Result<()> {
+    match args.command {
+        ConfigCommand::Show => {
+            for cfg in global.load_projects()? {
+                if let Some(name) = &cfg.project {
+                    println!("# [[project]] {}", name);
+                }
+                print!("{}", cfg.describe()?);
+            }
         }
     }
-    out
+    Ok(())
+}
+
+pub async fn handle_prompt(args: PromptArgs, global: &GlobalArgs) -> Result<()> {
+    let PromptCommand::Preview { key, locale } = args.command;
+    let cfg = global.load_config()?;
+    let sources = read_source(&cfg)?;
+    let source_flat = flatten_locale(&sources);
+    let english = source_flat
+        .get(&key)
+        .ok_or_else(|| anyhow!("No key {:?} in the {} source", key, cfg.source_locale))?;
+    let settings = cfg
+        .locales
+        .settings()
+        .get(&locale)
+        .cloned()
+        .unwrap_or_default();
+    let namespaces: Vec<Option<String>> = sources.iter().map(|f| f.namespace.clone()).collect();
+    let parent = fallback_values(
+        &cfg.file_pattern,
+        &namespaces,
+        &cfg.locales.fallback_chain(&locale),
+    )?
+    .remove(&key);
+    if let Some((parent_locale, text)) = &parent
+        && settings.fallback_mode.unwrap_or(FallbackMode::Copy) == FallbackMode::Copy
+    {
+        println!(
+            "No request: {} copies {:?} from {}",
+            locale, text, parent_locale
+        );
+        return Ok(());
+    }
+
+    let target = flatten_locale(&read_locale(&cfg.file_pattern, &locale, &namespaces)?);
+    let k = settings.examples.unwrap_or(cfg.translate.examples);
+    let state = TranslationState::load(Path::new(&cfg.state_file))?;
+    let examples = ExamplePool::new(&source_flat, &target, |path| {
+        state.is_untranslated(&locale, path)
+    })
+    .most_similar(&key, english, k);
+    let required = required_tokens(english, &cfg.translate.protected_terms);
+    let vars = PromptVars {
+        key: Some(&key),
+        source_locale: &cfg.source_locale,
+        target_locale: &locale,
+        text: english,
+        required: &required,
+        settings: &settings,
+        examples: &examples,
+    };
+    let (system, text) = Prompts::from_config(&cfg)?.request(vars, parent.as_ref());
+    let system = with_output_rule(&system, cfg.openai.response_format == ResponseFormat::Json);
+    let model = locale_model(&cfg, &locale).unwrap_or(&cfg.openai.model);
+    println!(
+        "--- system ({}) ---\n{}--- user ---\n{}",
+        model, system, text
+    );
+    Ok(())
 }
 
+pub async fn handle_init(args: InitArgs, global: &GlobalArgs) -> Result<()> {
+    let out = global
+        .config
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(CONFIG_FILE));
+    if out.exists() && !args.force {
+        return Err(anyhow!(
+            "{} already exists (use --force to overwrite)",
+            out.display()
+        ));
+    }
+    // file_pattern is relative to the config file, so scan from its directory
+    let dir = out
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let in_dir = |pattern: &str| {
+        if dir == Path::new(".") {
+            pattern.to_string()
+        } else {
+            dir.join(pattern).to_string_lossy().into_owned()
+        }
+    };
+
+    let layouts = infer_layouts(&scan_json_files(dir)?);
+    match layouts.first() {
+        Some(l) => println!(
+            "Found {} locale(s) matching {}",
+            l.locales.len(),
+            l.file_pattern
+        ),
+        None => println!("No locale files found; using the default layout"),
+    }
+    for other in layouts.iter().skip(1) {
+        println!(
+            "  also found: {} ({})",
+            other.file_pattern,
+            other.locales.join(", ")
+        );
+    }
+    let default_pattern = layouts
+        .first()
+        .map_or("locales/{locale}.json", |l| l.file_pattern.as_str());
+    let file_pattern = ask(args.yes, "File pattern", default_pattern)?;
+    if !file_pattern.contains("{locale}") {
+        return Err(anyhow!("file_pattern must contain {{locale}}"));
+    }
+    let pattern = in_dir(&file_pattern);
+
+    let found = discover_locales(&pattern)?;
+    let mut counts = BTreeMap::new();
+    for locale in &found {
+        let files: Vec<PathBuf> = discover_namespaces(&pattern, locale)?
+            .iter()
+            .map(|ns| namespace_path(&pattern, locale, ns.as_deref()))
+            .collect();
+        counts.insert(locale.clone(), count_strings(&files));
+    }
+    let guess = guess_source_locale(&counts).unwrap_or_else(|| Config::default().source_locale);
+    let source_locale = ask(args.yes, "Source locale", &guess)?;
+    let targets: Vec<String> = found
+        .iter()
+        .filter(|l| **l != source_locale)
+        .cloned()
+        .collect();
+    let locales = split_locales(&ask(
+        args.yes,
+        "Target locales (comma-separated)",
+        &targets.join(", "),
+    )?);
+    if let Some(bad) = std::iter::once(&source_locale)
+        .chain(&locales)
+        .find(|l| !is_locale_code(l))
+    {
+        return Err(anyhow!(
+            "{:?} is not a BCP 47 language tag (e.g. en-GB, pt-BR)",
+            bad
+        ));
+    }
+
+    let namespaces = discover_namespaces(&pattern, &source_locale)?;
+    // the largest source file says most about the formatting in use
+    let format = namespaces
+        .iter()
+        .filter_map(|ns| {
+            std::fs::read_to_string(namespace_path(&pattern, &source_locale, ns.as_deref())).ok()
+        })
+        .max_by_key(|text| text.len())
+        .map(|text| detect_format(&text))
+        .unwrap_or_default();
+    std::fs::write(
+        &out,
+        render_config(&file_pattern, &source_locale, &locales, &format),
+    )
+    .with_context(|| format!("Writing {:?}", out))?;
+    println!("Wrote {}", out.display());
+
+    let missing: Vec<PathBuf> = locales
+        .iter()
+        .flat_map(|l| {
+            namespaces
+                .iter()
+                .map(|ns| namespace_path(&pattern, l, ns.as_deref()))
+        })
+        .filter(|p| !p.exists())
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let create = args.create_missing
+        || (!args.yes
+            && ask(
+                false,
+                &format!("Create {} empty target file(s)?", missing.len()),
+                "y",
+            )?
+            .starts_with(['y', 'Y']));
+    if create {
+        for file in &missing {
+            write_json_atomic(file, &Value::Object(serde_json::Map::new()), &format)?;
+            println!("Created {}", file.display());
+        }
+    }
+    Ok(())
+}
 
+// Prompt on stdin with a default; `assume_default` (from --yes) skips the prompt
+fn ask(assume_default: bool, question: &str, default: &str) -> Result<String> {
+    if assume_default {
+        return Ok(default.to_string());
+    }
+    print!("{} [{}]: ", question, default);
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    let answer = line.trim();
+    Ok(if answer.is_empty() {
+        default.to_string()
+    } else {
+        answer.to_string()
+    })
+}
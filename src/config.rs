@@ -1,31 +1,364 @@
-use anyhow::{Context, Result};
+use crate::locales::is_locale_code;
+use anyhow::{Context, Result, anyhow};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     pub source_locale: String,
     pub file_pattern: String,
-    pub locales: Vec<String>,
+    pub locales: Locales,
     pub concurrency: usize,
+    /// Where translation bookkeeping (e.g. source hashes for staleness) is kept
+    pub state_file: String,
+    /// Progress of an unfinished translate run, used by `translate --resume`
+    pub checkpoint_file: String,
     pub openai: OpenAi,
     pub translate: Translate,
+    pub prompt: Prompt,
+    pub format: Format,
+    /// `[[project]]` tables for monorepos: a `name` plus any settings above, which override
+    /// the top-level ones for that project
+    #[serde(rename = "project", skip_serializing)]
+    pub projects: Vec<toml::Table>,
+    /// The project this config was resolved for (see `Config::projects`)
+    #[serde(skip)]
+    pub project: Option<String>,
+    /// Directory that relative file paths are resolved against
+    #[serde(skip)]
+    root: PathBuf,
+    /// Where each value came from (dotted path -> origin), for `config show`
+    #[serde(skip)]
+    pub provenance: BTreeMap<String, String>,
+    /// Environment and `--set` overrides, reapplied on top of each project table
+    #[serde(skip)]
+    overrides: Vec<Override>,
+}
+
+// A `RUSTYLANG_*` variable or `--set` value for one dotted config path
+#[derive(Debug, Clone)]
+struct Override {
+    path: String,
+    value: String,
+    origin: String,
+}
+
+// Target locales: `locales = ["fr-FR", "de-DE"]`, or one `[locales."de-DE"]` table per
+// locale whose settings override the top level when translating that locale
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Locales {
+    names: Vec<String>,
+    /// Only locales with at least one setting
+    settings: BTreeMap<String, LocaleSettings>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocaleSettings {
+    /// Used instead of `openai.model` for this locale
+    pub model: Option<String>,
+    /// Only `openai` is available
+    pub provider: Option<String>,
+    /// Sampling temperature (0-2); the API default otherwise
+    pub temperature: Option<f64>,
+    pub formality: Option<Formality>,
+    /// Extra instructions added to the prompt (spelling conventions, tone, terminology)
+    pub notes: Option<String>,
+    /// Cap on in-flight requests for this locale, below the top-level `concurrency`
+    pub concurrency: Option<usize>,
+    /// Parent locale whose translations seed missing keys, e.g. `fr-FR` for `fr-CA`; followed
+    /// through the parent's own fallback
+    pub fallback: Option<String>,
+    /// How parent translations are used; `copy` when unset
+    pub fallback_mode: Option<FallbackMode>,
+    /// Only write values that differ from the parent, leaving the rest to the runtime's fallback
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub diff_only: bool,
+    /// Required translations of source terms (`cart = "Warenkorb"`); only terms found in the
+    /// source text are added to the prompt
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub glossary: BTreeMap<String, String>,
+    /// Overrides `translate.examples` for this locale
+    pub examples: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FallbackMode {
+    /// Use the parent text as is
+    Copy,
+    /// Ask the model to adapt the parent text to the regional variant
+    Adapt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Formality {
+    /// Polite form of address, e.g. German "Sie"
+    Formal,
+    /// Familiar form of address, e.g. Dutch "je"
+    Informal,
+}
+
+impl Locales {
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn settings(&self) -> &BTreeMap<String, LocaleSettings> {
+        &self.settings
+    }
+
+    pub fn settings_mut(&mut self) -> impl Iterator<Item = &mut LocaleSettings> {
+        self.settings.values_mut()
+    }
+
+    // Parents to take missing keys from, nearest first (fr-CA -> [fr-FR, ...]); stops on a cycle
+    pub fn fallback_chain(&self, locale: &str) -> Vec<String> {
+        let mut chain: Vec<String> = Vec::new();
+        let mut current = locale;
+        while let Some(parent) = self
+            .settings
+            .get(current)
+            .and_then(|s| s.fallback.as_deref())
+        {
+            if parent == locale || chain.iter().any(|c| c == parent) {
+                break;
+            }
+            chain.push(parent.to_string());
+            current = parent;
+        }
+        chain
+    }
+}
+
+impl From<Vec<String>> for Locales {
+    fn from(names: Vec<String>) -> Self {
+        Self {
+            names,
+            settings: BTreeMap::new(),
+        }
+    }
+}
+
+impl Serialize for Locales {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        if self.settings.is_empty() {
+            return self.names.serialize(serializer);
+        }
+        let mut map = serializer.serialize_map(Some(self.names.len()))?;
+        for name in &self.names {
+            map.serialize_entry(name, &self.settings.get(name).cloned().unwrap_or_default())?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Locales {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        match toml::Value::deserialize(deserializer)? {
+            toml::Value::Array(items) => {
+                let names: Vec<String> = toml::Value::Array(items)
+                    .try_into()
+                    .map_err(D::Error::custom)?;
+                Ok(Self::from(names))
+            }
+            toml::Value::Table(table) => {
+                let mut locales = Self::default();
+                for (name, value) in table {
+                    let settings: LocaleSettings = value
+                        .try_into()
+                        .map_err(|e| D::Error::custom(format!("locales.{}: {}", name, e)))?;
+                    if settings != LocaleSettings::default() {
+                        locales.settings.insert(name.clone(), settings);
+                    }
+                    locales.names.push(name);
+                }
+                Ok(locales)
+            }
+            other => Err(D::Error::custom(format!(
+                "locales must be a list of locale codes or [locales.\"<code>\"] tables, found {}",
+                other.type_str()
+            ))),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
+#[serde(default, deny_unknown_fields)]
 pub struct OpenAi {
     pub model: String,
+    /// Plaintext key; prefer OPENAI_API_KEY, `api_key_command` or `api_key_file`
     pub api_key: Option<String>,
+    /// Command whose output is the key, e.g. `pass show openai`; run through the shell
+    pub api_key_command: Option<String>,
+    /// File holding only the key, relative to the config file
+    pub api_key_file: Option<String>,
+    /// Per-model prices used by `translate --estimate`, keyed by model name or prefix
+    pub prices: BTreeMap<String, ModelPrice>,
+    /// Stop sending requests once a run reaches these limits
+    pub budget: BudgetLimits,
+    /// Retries for throttled (429), timed out and 5xx requests
+    pub max_retries: u32,
+    /// First backoff step; doubles per attempt up to `retry_max_ms`, with jitter
+    pub retry_base_ms: u64,
+    /// Longest wait between attempts, server-provided waits included
+    pub retry_max_ms: u64,
+    /// Organisation quotas; requests wait for capacity instead of hitting 429s
+    pub requests_per_minute: Option<u64>,
+    /// Counted from estimated prompt + completion tokens per request
+    pub tokens_per_minute: Option<u64>,
+    /// How replies are requested; models that reject JSON mode fall back to text automatically
+    pub response_format: ResponseFormat,
+    /// Sampling temperature (0-2); only sent to models that support it
+    pub temperature: Option<f64>,
+    /// Nucleus sampling (0-1]; only sent to models that support it
+    pub top_p: Option<f64>,
+    /// Best-effort deterministic sampling
+    pub seed: Option<i64>,
+    /// Cap on reply tokens per request, reasoning tokens included
+    pub max_output_tokens: Option<u64>,
+    /// For reasoning models (o-series, gpt-5); ignored for others
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Whole request, from connecting to reading the reply
+    pub timeout_secs: u64,
+    pub connect_timeout_secs: u64,
+    /// Proxy for API requests, e.g. `http://proxy.corp:3128`; HTTPS_PROXY is used when unset
+    pub proxy: Option<String>,
+    /// Hosts that bypass `proxy` (`NO_PROXY` syntax: domains, IPs, CIDR ranges)
+    pub no_proxy: Vec<String>,
+    /// PEM files with extra root certificates to trust, e.g. a TLS-inspecting proxy's CA
+    pub ca_certs: Vec<String>,
+    /// PEM client certificate for mutual TLS; may also hold the private key
+    pub client_cert: Option<String>,
+    /// PEM private key for `client_cert`
+    pub client_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Minimal,
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseFormat {
+    /// `{"translation": "..."}` via structured outputs, parsed strictly
+    Json,
+    /// Plain text; the first non-empty line is used, without surrounding quotes
+    Text,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BudgetLimits {
+    pub max_tokens: Option<u64>,
+    /// USD, priced with `prices`/built-in model prices
+    pub max_cost: Option<f64>,
+}
+
+/// USD per 1M tokens
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
+#[serde(default, deny_unknown_fields)]
 pub struct Translate {
     pub overwrite_existing: bool,
     pub preserve_placeholders: bool,
+    /// Remove target keys that no longer exist in the source when translating
+    pub prune_orphans: bool,
+    /// Key patterns (globs, or `re:` regexes) that prune never removes
+    pub prune_keep: Vec<String>,
+    /// Only translate keys matching one of these patterns (empty = all keys)
+    pub include: Vec<String>,
+    /// Never translate keys matching these patterns
+    pub exclude: Vec<String>,
+    /// Keys whose source text is copied to every locale unchanged (brand names, legal text, URLs)
+    pub verbatim: Vec<String>,
+    /// Terms that must appear untranslated wherever they occur in the source text
+    pub protected_terms: Vec<String>,
+    /// Existing translations of similar keys included in each prompt as examples (0 = none)
+    pub examples: usize,
+    /// Save translate progress to the checkpoint file every N completed keys (0 = only on exit)
+    pub checkpoint_every: usize,
+    /// What to do with a key whose translation request failed
+    pub on_failure: FailurePolicy,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Prompt {
+    /// System prompt template for translation requests (built-in when unset)
+    pub template_file: Option<String>,
+    /// System prompt template for `fallback_mode = "adapt"` requests
+    pub adapt_template_file: Option<String>,
+    /// Sent with every request: tone, brand voice, units, date formats
+    pub style_guide: Option<String>,
+    /// Style guide read from a file, appended to `style_guide`
+    pub style_guide_file: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum FailurePolicy {
+    /// Leave the key missing so the next run retries it
+    Skip,
+    /// Write the source text, marked untranslated in the state file so it is retried
+    Source,
+    /// Stop the run; finished translations are still written
+    Abort,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Format {
+    /// Spaces per indentation level
+    pub indent: usize,
+    pub sort_keys: SortKeys,
+    /// Write non-ASCII characters as \uXXXX escapes
+    pub escape_unicode: bool,
+    pub newline: Newline,
+    /// End files with a newline
+    pub final_newline: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKeys {
+    /// Keep keys in the order they appear in the file
+    None,
+    /// Sort object keys alphabetically at every level
+    Alphabetical,
+    /// Mirror the key order of the source locale file
+    Source,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Newline {
+    Lf,
+    Crlf,
 }
 
 impl Default for Config {
@@ -33,37 +366,937 @@ impl Default for Config {
         Self {
             source_locale: "en-GB".to_string(),
             file_pattern: "{locale}.json".to_string(),
-            locales: vec![],
+            locales: Locales::default(),
             concurrency: 50,
+            state_file: ".rustylang-state.json".to_string(),
+            checkpoint_file: ".rustylang-checkpoint.json".to_string(),
             openai: OpenAi::default(),
             translate: Translate::default(),
+            prompt: Prompt::default(),
+            format: Format::default(),
+            projects: vec![],
+            project: None,
+            root: PathBuf::new(),
+            provenance: BTreeMap::new(),
+            overrides: vec![],
         }
     }
 }
 
 impl Default for OpenAi {
     fn default() -> Self {
-        Self { model: "gpt-5-nano-2025-08-07".to_string(), api_key: None }
+        Self {
+            model: "gpt-5-nano-2025-08-07".to_string(),
+            api_key: None,
+            api_key_command: None,
+            api_key_file: None,
+            prices: BTreeMap::new(),
+            budget: BudgetLimits::default(),
+            max_retries: 5,
+            retry_base_ms: 500,
+            retry_max_ms: 30_000,
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            response_format: ResponseFormat::Json,
+            temperature: None,
+            top_p: None,
+            seed: None,
+            max_output_tokens: None,
+            reasoning_effort: None,
+            timeout_secs: 30,
+            connect_timeout_secs: 10,
+            proxy: None,
+            no_proxy: vec![],
+            ca_certs: vec![],
+            client_cert: None,
+            client_key: None,
+        }
     }
 }
 
 impl Default for Translate {
     fn default() -> Self {
-        Self { overwrite_existing: false, preserve_placeholders: true }
+        Self {
+            overwrite_existing: false,
+            preserve_placeholders: true,
+            prune_orphans: false,
+            prune_keep: vec![],
+            include: vec![],
+            exclude: vec![],
+            verbatim: vec![],
+            protected_terms: vec![],
+            examples: 0,
+            checkpoint_every: 50,
+            on_failure: FailurePolicy::Skip,
+        }
     }
 }
 
-pub fn load_config() -> Result<Config> {
-    let path = PathBuf::from("rustylang.toml");
-    if !path.exists() {
-        return Ok(Config::default());
+impl Default for Format {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            sort_keys: SortKeys::Alphabetical,
+            escape_unicode: false,
+            newline: Newline::Lf,
+            final_newline: false,
+        }
+    }
+}
+
+pub const CONFIG_FILE: &str = "rustylang.toml";
+const ENV_PREFIX: &str = "RUSTYLANG_";
+
+// Load `explicit`, or else the nearest rustylang.toml from the working directory up to the
+// repository root, then apply `RUSTYLANG_*` environment variables and `--set` values on top.
+// Relative file paths in the config are resolved against `root`, which defaults to the
+// directory holding the config file, once `Config::projects` is called.
+pub fn load_config(
+    explicit: Option<&Path>,
+    root: Option<&Path>,
+    sets: &[(String, String)],
+) -> Result<Config> {
+    let path = match explicit {
+        Some(p) if !p.is_file() => return Err(anyhow!("Config file {:?} not found", p)),
+        Some(p) => Some(p.to_path_buf()),
+        None => find_config()?,
+    };
+
+    // RUSTYLANG_OPENAI__MAX_RETRIES=3 sets openai.max_retries; `--set` wins over the environment
+    let mut env_vars: Vec<(String, String)> = env::vars()
+        .filter(|(k, _)| k.starts_with(ENV_PREFIX))
+        .collect();
+    env_vars.sort();
+    // other tools' variables that share the prefix are skipped; a misspelled --set still fails
+    let mut overrides: Vec<Override> = env_vars
+        .into_iter()
+        .map(|(k, value)| Override {
+            path: k[ENV_PREFIX.len()..].to_lowercase().replace("__", "."),
+            value,
+            origin: format!("env {}", k),
+        })
+        .filter(|o| {
+            let known = is_config_path(&o.path);
+            if !known {
+                warn!(
+                    "Ignoring {}: {:?} is not a config setting",
+                    o.origin, o.path
+                );
+            }
+            known
+        })
+        .collect();
+    overrides.extend(sets.iter().map(|(k, v)| Override {
+        path: k.clone(),
+        value: v.clone(),
+        origin: "--set".to_string(),
+    }));
+
+    let mut provenance = BTreeMap::new();
+    let table = match &path {
+        Some(path) => {
+            let contents = fs::read_to_string(path)
+                .with_context(|| format!("Reading config file {:?}", path))?;
+            // Deserializing the file on its own first keeps line numbers in error messages
+            toml::from_str::<Config>(&contents)
+                .map_err(explain)
+                .with_context(|| format!("Parsing config file {:?}", path))?;
+            let mut table: toml::Table = toml::from_str(&contents)?;
+            let projects = table.remove("project");
+            record_leaves(&table, "", &path.display().to_string(), &mut provenance);
+            if let Some(projects) = projects {
+                table.insert("project".to_string(), projects);
+            }
+            info!(config=?path, "Loaded config");
+            table
+        }
+        None => {
+            warn!(
+                "No {} found in this directory or any parent up to the repository root; \
+                 using built-in defaults",
+                CONFIG_FILE
+            );
+            toml::Table::new()
+        }
+    };
+
+    let mut cfg = apply_overrides(table, &overrides, &mut provenance)?;
+    cfg.provenance = provenance;
+    cfg.overrides = overrides;
+    cfg.root = match (root, &path) {
+        (Some(root), _) => root.to_path_buf(),
+        (None, Some(path)) => path.parent().unwrap_or(Path::new("")).to_path_buf(),
+        (None, None) => PathBuf::new(),
+    };
+    // the API client is built from the top level only, so its files are resolved here once
+    let ai = &mut cfg.openai;
+    for file in ai
+        .ca_certs
+        .iter_mut()
+        .chain(&mut ai.client_cert)
+        .chain(&mut ai.client_key)
+        .chain(&mut ai.api_key_file)
+    {
+        *file = cfg.root.join(&*file).to_string_lossy().into_owned();
     }
-    let contents = fs::read_to_string(&path)
-        .with_context(|| format!("Reading config file {:?}", path))?;
-    let cfg: Config = toml::from_str(&contents)
-        .with_context(|| format!("Parsing config file {:?}", path))?;
     Ok(cfg)
 }
 
+fn apply_overrides(
+    mut table: toml::Table,
+    overrides: &[Override],
+    provenance: &mut BTreeMap<String, String>,
+) -> Result<Config> {
+    if overrides.is_empty() {
+        return table.try_into().map_err(explain);
+    }
+    let defaults = toml::Table::try_from(Config::default())?;
+    for o in overrides {
+        let keys: Vec<&str> = o.path.split('.').collect();
+        let mut value = parse_override(&o.value);
+        // comma-separated lists for array settings, e.g. RUSTYLANG_LOCALES=fr-FR,de-DE
+        if let toml::Value::String(text) = &value
+            && lookup(&defaults, &keys).is_some_and(|d| d.is_array())
+        {
+            value = toml::Value::Array(
+                text.split(',')
+                    .map(|s| s.trim())
+                    .filter(|s| !s.is_empty())
+                    .map(toml::Value::from)
+                    .collect(),
+            );
+        }
+        set_path(&mut table, &keys, value)
+            .with_context(|| format!("Applying {} ({})", o.path, o.origin))?;
+        provenance.insert(o.path.clone(), o.origin.clone());
+    }
+    let origins: Vec<&str> = overrides.iter().map(|o| o.origin.as_str()).collect();
+    table
+        .try_into()
+        .map_err(explain)
+        .with_context(|| format!("Applying overrides from {}", origins.join(", ")))
+}
+
+// Whether a dotted path names a setting. Deserializing it on its own only fails with "unknown
+// field" for paths that don't exist; a value of the wrong type still means a known setting.
+fn is_config_path(path: &str) -> bool {
+    let keys: Vec<&str> = path.split('.').collect();
+    let mut table = toml::Table::new();
+    if keys.iter().any(|k| k.is_empty())
+        || set_path(&mut table, &keys, toml::Value::String(String::new())).is_err()
+    {
+        return false;
+    }
+    toml::Value::Table(table)
+        .try_into::<Config>()
+        .err()
+        .is_none_or(|e| !e.to_string().contains("unknown field"))
+}
+
+// TOML syntax when the value parses as TOML (numbers, booleans, arrays), else a plain string
+fn parse_override(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("v = {}", raw))
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
 
+fn lookup<'a>(table: &'a toml::Table, keys: &[&str]) -> Option<&'a toml::Value> {
+    let (last, parents) = keys.split_last()?;
+    let mut t = table;
+    for k in parents {
+        t = t.get(*k)?.as_table()?;
+    }
+    t.get(*last)
+}
 
+fn set_path(table: &mut toml::Table, keys: &[&str], value: toml::Value) -> Result<()> {
+    let Some((last, parents)) = keys.split_last() else {
+        return Err(anyhow!("Empty config key"));
+    };
+    let mut t = table;
+    for k in parents {
+        let entry = t
+            .entry(k.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        t = entry
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("{} is not a table", k))?;
+    }
+    t.insert(last.to_string(), value);
+    Ok(())
+}
+
+// Record `origin` for every leaf value (scalars and arrays) under `prefix`
+fn record_leaves(
+    table: &toml::Table,
+    prefix: &str,
+    origin: &str,
+    provenance: &mut BTreeMap<String, String>,
+) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            toml::Value::Table(t) if !t.is_empty() => record_leaves(t, &path, origin, provenance),
+            _ => {
+                provenance.insert(path, origin.to_string());
+            }
+        }
+    }
+}
+
+// Turn "unknown field `sorce_locale`, expected one of ..." into a did-you-mean hint
+fn explain(err: toml::de::Error) -> anyhow::Error {
+    let msg = err.to_string();
+    let field = Regex::new(r"unknown field `([^`]+)`, expected (.*)").unwrap();
+    let Some(caps) = field.captures(&msg) else {
+        return err.into();
+    };
+    let candidates: Vec<String> = Regex::new(r"`([^`]+)`")
+        .unwrap()
+        .captures_iter(&caps[2])
+        .map(|c| c[1].to_string())
+        .collect();
+    let unknown = &caps[1];
+    let best = candidates
+        .iter()
+        .map(|c| (edit_distance(unknown, c), c))
+        .filter(|(d, c)| *d <= (c.len() / 3).max(2))
+        .min();
+    match best {
+        Some((_, c)) => anyhow!("{}\ndid you mean `{}`?", msg.trim_end(), c),
+        None => err.into(),
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            cur.push(
+                (prev[j] + usize::from(ca != *cb))
+                    .min(prev[j + 1] + 1)
+                    .min(cur[j] + 1),
+            );
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+// Nearest config file walking up from the working directory, as a relative path so messages
+// about the files it points to stay short. The search stops at the first directory with `.git`.
+fn find_config() -> Result<Option<PathBuf>> {
+    let cwd = env::current_dir().context("Getting the working directory")?;
+    Ok(find_config_from(&cwd))
+}
+
+fn find_config_from(cwd: &Path) -> Option<PathBuf> {
+    for (depth, dir) in cwd.ancestors().enumerate() {
+        if dir.join(CONFIG_FILE).is_file() {
+            let mut rel: PathBuf = std::iter::repeat_n("..", depth).collect();
+            rel.push(CONFIG_FILE);
+            return Some(rel);
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+impl Config {
+    // Directory relative config paths are resolved against
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    // The effective config of each project to work on, with file paths resolved. Without
+    // `[[project]]` tables that is just this config. Each project gets its own state and
+    // checkpoint file unless it sets one, so their bookkeeping never mixes.
+    pub fn projects(&self, selected: Option<&str>) -> Result<Vec<Config>> {
+        if self.projects.is_empty() {
+            if let Some(name) = selected {
+                return Err(anyhow!(
+                    "No project {:?}: the config has no [[project]] tables",
+                    name
+                ));
+            }
+            let mut cfg = self.clone();
+            cfg.resolve_paths();
+            cfg.validate()?;
+            return Ok(vec![cfg]);
+        }
+
+        let base = toml::Table::try_from(self).context("Serializing config")?;
+        let mut names = Vec::new();
+        let mut out = Vec::new();
+        for table in &self.projects {
+            let name = table
+                .get("name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow!("[[project]] table without a name"))?
+                .to_string();
+            names.push(name.clone());
+            // one translator serves every project, so a per-project [openai] would be silently
+            // ignored
+            if table.contains_key("openai") {
+                return Err(anyhow!(
+                    "[[project]] {:?}: [project.openai] is not supported; \
+                     [openai] settings apply to the whole run",
+                    name
+                ));
+            }
+            if selected.is_some_and(|s| s != name) {
+                continue;
+            }
+
+            let mut merged = base.clone();
+            let mut overrides = table.clone();
+            overrides.remove("name");
+            for key in ["state_file", "checkpoint_file"] {
+                if !overrides.contains_key(key) {
+                    let inherited = merged.get(key).and_then(|v| v.as_str()).unwrap_or_default();
+                    overrides.insert(key.to_string(), project_file(inherited, &name).into());
+                }
+            }
+            let mut provenance = self.provenance.clone();
+            // a project's locales replace the top-level set in both the list and the table form
+            if overrides.contains_key("locales") {
+                merged.remove("locales");
+                provenance.retain(|key, _| key != "locales" && !key.starts_with("locales."));
+            }
+            record_leaves(
+                &overrides,
+                "",
+                &format!("[[project]] {}", name),
+                &mut provenance,
+            );
+            merge_tables(&mut merged, overrides);
+            // environment and --set values still win over the project table
+            let mut cfg = apply_overrides(merged, &self.overrides, &mut provenance)
+                .with_context(|| format!("Parsing [[project]] {:?}", name))?;
+            cfg.project = Some(name);
+            cfg.root = self.root.clone();
+            cfg.provenance = provenance;
+            cfg.resolve_paths();
+            cfg.validate()?;
+            out.push(cfg);
+        }
+        if out.is_empty() {
+            return Err(anyhow!(
+                "No project {:?} (configured: {})",
+                selected.unwrap_or_default(),
+                names.join(", ")
+            ));
+        }
+        Ok(out)
+    }
+
+    // Range and format checks that serde can't express, reporting every problem at once
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+        if self.concurrency == 0 {
+            problems.push("concurrency must be at least 1".to_string());
+        }
+        if !self.file_pattern.contains("{locale}") {
+            problems.push(format!(
+                "file_pattern {:?} must contain {{locale}}",
+                self.file_pattern
+            ));
+        }
+        for locale in std::iter::once(&self.source_locale).chain(self.locales.names()) {
+            if !is_locale_code(locale) {
+                problems.push(format!(
+                    "{:?} is not a BCP 47 language tag (e.g. en-GB, pt-BR, zh-Hant)",
+                    locale
+                ));
+            }
+        }
+        for (locale, s) in self.locales.settings() {
+            if s.model.as_ref().is_some_and(|m| m.trim().is_empty()) {
+                problems.push(format!("locales.{}.model must not be empty", locale));
+            }
+            if let Some(p) = s.provider.as_ref().filter(|p| *p != "openai") {
+                problems.push(format!(
+                    "locales.{}.provider {:?} is not supported (only \"openai\")",
+                    locale, p
+                ));
+            }
+            if s.temperature.is_some_and(|t| !(0.0..=2.0).contains(&t)) {
+                problems.push(format!(
+                    "locales.{}.temperature must be between 0 and 2",
+                    locale
+                ));
+            }
+            if s.concurrency == Some(0) {
+                problems.push(format!("locales.{}.concurrency must be at least 1", locale));
+            }
+            match &s.fallback {
+                Some(parent) if !is_locale_code(parent) => {
+                    problems.push(format!(
+                        "locales.{}.fallback {:?} is not a BCP 47 language tag",
+                        locale, parent
+                    ));
+                }
+                Some(_) => {
+                    let chain = self.locales.fallback_chain(locale);
+                    let last = chain.last().map(String::as_str).unwrap_or(locale);
+                    if self
+                        .locales
+                        .settings()
+                        .get(last)
+                        .and_then(|s| s.fallback.as_ref())
+                        .is_some()
+                    {
+                        problems.push(format!(
+                            "locales.{}.fallback forms a cycle ({} -> {})",
+                            locale,
+                            locale,
+                            chain.join(" -> ")
+                        ));
+                    }
+                }
+                None if s.fallback_mode.is_some() || s.diff_only => {
+                    problems.push(format!(
+                        "locales.{}: fallback_mode and diff_only require a fallback",
+                        locale
+                    ));
+                }
+                None => {}
+            }
+        }
+        if self.format.indent > 16 {
+            problems.push("format.indent must be at most 16".to_string());
+        }
+        let ai = &self.openai;
+        if ai.model.trim().is_empty() {
+            problems.push("openai.model must not be empty".to_string());
+        }
+        if ai.retry_base_ms > ai.retry_max_ms {
+            problems.push("openai.retry_base_ms must not exceed openai.retry_max_ms".to_string());
+        }
+        if ai.temperature.is_some_and(|t| !(0.0..=2.0).contains(&t)) {
+            problems.push("openai.temperature must be between 0 and 2".to_string());
+        }
+        if ai.top_p.is_some_and(|p| !(p > 0.0 && p <= 1.0)) {
+            problems.push("openai.top_p must be in (0, 1]".to_string());
+        }
+        if ai.max_output_tokens == Some(0) {
+            problems.push("openai.max_output_tokens must be at least 1".to_string());
+        }
+        if ai.timeout_secs == 0 {
+            problems.push("openai.timeout_secs must be at least 1".to_string());
+        }
+        if ai.connect_timeout_secs == 0 {
+            problems.push("openai.connect_timeout_secs must be at least 1".to_string());
+        }
+        if !ai.no_proxy.is_empty() && ai.proxy.is_none() {
+            problems.push(
+                "openai.no_proxy needs openai.proxy (NO_PROXY applies to HTTPS_PROXY)".to_string(),
+            );
+        }
+        if [
+            ai.api_key.is_some(),
+            ai.api_key_command.is_some(),
+            ai.api_key_file.is_some(),
+        ]
+        .iter()
+        .filter(|set| **set)
+        .count()
+            > 1
+        {
+            problems.push(
+                "set only one of openai.api_key, openai.api_key_command and openai.api_key_file"
+                    .to_string(),
+            );
+        }
+        if ai.client_key.is_some() && ai.client_cert.is_none() {
+            problems.push("openai.client_key needs openai.client_cert".to_string());
+        }
+        if ai.requests_per_minute == Some(0) {
+            problems.push("openai.requests_per_minute must be at least 1".to_string());
+        }
+        if ai.tokens_per_minute == Some(0) {
+            problems.push("openai.tokens_per_minute must be at least 1".to_string());
+        }
+        if ai.budget.max_tokens == Some(0) {
+            problems.push("openai.budget.max_tokens must be at least 1".to_string());
+        }
+        if ai.budget.max_cost.is_some_and(|c| c.is_nan() || c <= 0.0) {
+            problems.push("openai.budget.max_cost must be greater than 0".to_string());
+        }
+        for (model, price) in &ai.prices {
+            if price.input < 0.0 || price.output < 0.0 {
+                problems.push(format!("openai.prices.{:?} must not be negative", model));
+            }
+        }
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let label = self
+            .project
+            .as_ref()
+            .map(|p| format!(" (project {})", p))
+            .unwrap_or_default();
+        Err(anyhow!(
+            "Invalid config{}:\n  - {}",
+            label,
+            problems.join("\n  - ")
+        ))
+    }
+
+    // The effective settings as `dotted.key = value  # origin` lines, for `config show`
+    pub fn describe(&self) -> Result<String> {
+        let mut table = toml::Table::try_from(self)?;
+        if let Some(toml::Value::Table(ai)) = table.get_mut("openai")
+            && ai.contains_key("api_key")
+        {
+            ai.insert("api_key".to_string(), "<redacted>".into());
+        }
+        let mut leaves = BTreeMap::new();
+        collect_leaves(&table, "", &mut leaves);
+        let width = leaves
+            .iter()
+            .map(|(k, v)| k.len() + v.len())
+            .max()
+            .unwrap_or(0)
+            + 3;
+        let mut out = String::new();
+        for (key, value) in &leaves {
+            let origin = self
+                .provenance
+                .get(key)
+                .map(String::as_str)
+                .unwrap_or("default");
+            let line = format!("{} = {}", key, value);
+            out.push_str(&format!("{:<width$}  # {}\n", line, origin, width = width));
+        }
+        Ok(out)
+    }
+
+    fn resolve_paths(&mut self) {
+        let root = &self.root;
+        if root.as_os_str().is_empty() {
+            return;
+        }
+        let resolve = |p: &str| {
+            if Path::new(p).is_absolute() {
+                p.to_string()
+            } else {
+                root.join(p).to_string_lossy().into_owned()
+            }
+        };
+        self.file_pattern = resolve(&self.file_pattern);
+        self.state_file = resolve(&self.state_file);
+        self.checkpoint_file = resolve(&self.checkpoint_file);
+        let prompt = &mut self.prompt;
+        for path in [
+            &mut prompt.template_file,
+            &mut prompt.adapt_template_file,
+            &mut prompt.style_guide_file,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *path = resolve(path);
+        }
+    }
+}
+
+fn collect_leaves(table: &toml::Table, prefix: &str, out: &mut BTreeMap<String, String>) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            toml::Value::Table(t) if !t.is_empty() => collect_leaves(t, &path, out),
+            v => {
+                out.insert(path, v.to_string());
+            }
+        }
+    }
+}
+
+// `.rustylang-state.json` -> `.rustylang-state.web.json` for project `web`
+fn project_file(path: &str, project: &str) -> String {
+    match path.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.contains('/') => {
+            format!("{}.{}.{}", stem, project, ext)
+        }
+        _ => format!("{}.{}", path, project),
+    }
+}
+
+// Recursively overlay `overrides` on `base`: nested tables merge, anything else replaces
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(b)), toml::Value::Table(o)) => merge_tables(b, o),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn projects_override_top_level_settings() {
+        let cfg: Config = toml::from_str(
+            r#"
+            source_locale = "en-GB"
+            locales = ["fr-FR"]
+            [translate]
+            checkpoint_every = 10
+            [[project]]
+            name = "web"
+            file_pattern = "web/{locale}.json"
+            [[project]]
+            name = "mobile"
+            source_locale = "en-US"
+            [project.translate]
+            prune_orphans = true
+            "#,
+        )
+        .unwrap();
+        let projects = cfg.projects(None).unwrap();
+        assert_eq!(projects.len(), 2);
+        let (web, mobile) = (&projects[0], &projects[1]);
+        assert_eq!(
+            (web.source_locale.as_str(), web.file_pattern.as_str()),
+            ("en-GB", "web/{locale}.json")
+        );
+        assert_eq!(web.state_file, ".rustylang-state.web.json");
+        assert_eq!(mobile.source_locale, "en-US");
+        assert!(mobile.translate.prune_orphans);
+        assert_eq!(mobile.translate.checkpoint_every, 10);
+        assert_eq!(cfg.projects(Some("mobile")).unwrap().len(), 1);
+        assert!(cfg.projects(Some("api")).is_err());
+        let cfg: Config =
+            toml::from_str("[[project]]\nname = \"web\"\n[project.openai]\nmodel = \"gpt-4o\"\n")
+                .unwrap();
+        assert!(
+            cfg.projects(None)
+                .unwrap_err()
+                .to_string()
+                .contains("[project.openai] is not supported")
+        );
+    }
+
+    #[test]
+    fn project_locales_replace_the_top_level_set() {
+        let cfg: Config = toml::from_str(
+            r#"
+            [locales."de-DE"]
+            formality = "formal"
+            [locales."ja-JP"]
+            [locales."fr-FR"]
+            notes = "top level"
+            [[project]]
+            name = "web"
+            [project.locales."fr-FR"]
+            formality = "informal"
+            [[project]]
+            name = "mobile"
+            locales = ["es-ES"]
+            [[project]]
+            name = "docs"
+            "#,
+        )
+        .unwrap();
+        let projects = cfg.projects(None).unwrap();
+        let web = &projects[0].locales;
+        assert_eq!(web.names(), ["fr-FR"]);
+        assert_eq!(
+            (
+                web.settings()["fr-FR"].formality,
+                web.settings()["fr-FR"].notes.as_deref()
+            ),
+            (Some(Formality::Informal), None)
+        );
+        assert_eq!(projects[1].locales.names(), ["es-ES"]);
+        assert_eq!(projects[2].locales.names(), ["de-DE", "fr-FR", "ja-JP"]);
+    }
+
+    #[test]
+    fn locales_accept_a_list_or_per_locale_tables() {
+        let cfg: Config = toml::from_str(
+            r#"
+            [locales."de-DE"]
+            formality = "formal"
+            model = "gpt-4o"
+            [locales."nl-NL"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cfg.locales.names(), ["de-DE", "nl-NL"]);
+        assert_eq!(
+            cfg.locales.settings()["de-DE"].formality,
+            Some(Formality::Formal)
+        );
+        assert!(!cfg.locales.settings().contains_key("nl-NL"));
+        // round-trips through the table form used for [[project]] merging
+        let again: Config = toml::Table::try_from(&cfg).unwrap().try_into().unwrap();
+        assert_eq!(again.locales, cfg.locales);
+
+        let cyclic: Config = toml::from_str(
+            r#"
+            [locales."fr-CA"]
+            fallback = "fr-FR"
+            [locales."fr-FR"]
+            fallback = "fr-CA"
+            [locales."pt-BR"]
+            fallback = "pt-PT"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cyclic.locales.fallback_chain("pt-BR"), ["pt-PT"]);
+        assert!(
+            cyclic
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("locales.fr-CA.fallback forms a cycle")
+        );
+
+        let err = toml::from_str::<Config>("[locales.\"de-DE\"]\nformalty = \"formal\"")
+            .map_err(explain)
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("did you mean `formality`?"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn discovery_stops_at_the_repository_root() {
+        let dir = crate::test_support::temp_dir("discovery");
+        let nested = dir.join("repo/apps/web");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(dir.join(CONFIG_FILE), "").unwrap();
+        assert_eq!(
+            find_config_from(&nested),
+            Some(PathBuf::from("../../../rustylang.toml"))
+        );
+        fs::create_dir(dir.join("repo/.git")).unwrap();
+        assert_eq!(find_config_from(&nested), None);
+        fs::write(dir.join("repo/apps").join(CONFIG_FILE), "").unwrap();
+        assert_eq!(
+            find_config_from(&nested),
+            Some(PathBuf::from("../rustylang.toml"))
+        );
+    }
+
+    #[test]
+    fn paths_resolve_against_the_config_directory_or_root() {
+        let dir = crate::test_support::temp_dir("resolve");
+        fs::create_dir(dir.join("config")).unwrap();
+        let path = dir.join("config").join(CONFIG_FILE);
+        let toml = "file_pattern = \"i18n/{locale}.json\"\nstate_file = \"/abs/state.json\"\n\
+                    [openai]\nca_certs = [\"ca.pem\"]\n";
+        fs::write(&path, toml).unwrap();
+        let cfg = load_config(Some(&path), None, &[])
+            .unwrap()
+            .projects(None)
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            Path::new(&cfg.file_pattern),
+            dir.join("config/i18n/{locale}.json")
+        );
+        assert_eq!(cfg.state_file, "/abs/state.json");
+        assert_eq!(
+            Path::new(&cfg.openai.ca_certs[0]),
+            dir.join("config/ca.pem")
+        );
+        let cfg = load_config(Some(&path), Some(&dir), &[])
+            .unwrap()
+            .projects(None)
+            .unwrap()
+            .remove(0);
+        assert_eq!(Path::new(&cfg.file_pattern), dir.join("i18n/{locale}.json"));
+        let err = load_config(Some(&dir.join("missing.toml")), None, &[]).unwrap_err();
+        assert!(err.to_string().contains("not found"), "{}", err);
+    }
+
+    #[test]
+    fn only_environment_variables_naming_a_setting_are_overrides() {
+        assert!(
+            is_config_path("concurrency")
+                && is_config_path("openai.max_retries")
+                && is_config_path("openai.temperature")
+        );
+        assert!(!is_config_path("foo") && !is_config_path("log") && !is_config_path("openai.nope"));
+        // --set keeps failing on unknown paths
+        let typo = Override {
+            path: "foo".into(),
+            value: "1".into(),
+            origin: "--set".into(),
+        };
+        assert!(apply_overrides(toml::Table::new(), &[typo], &mut BTreeMap::new()).is_err());
+    }
+
+    #[test]
+    fn unknown_fields_get_a_suggestion() {
+        let err = toml::from_str::<Config>("sorce_locale = \"fr-FR\"")
+            .map_err(explain)
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("did you mean `source_locale`?"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn overrides_parse_types_and_record_origin() {
+        let overrides = [
+            Override {
+                path: "concurrency".into(),
+                value: "4".into(),
+                origin: "env RUSTYLANG_CONCURRENCY".into(),
+            },
+            Override {
+                path: "locales".into(),
+                value: "fr-FR, de-DE".into(),
+                origin: "--set".into(),
+            },
+            Override {
+                path: "openai.model".into(),
+                value: "gpt-4o-mini".into(),
+                origin: "--set".into(),
+            },
+        ];
+        let mut provenance = BTreeMap::new();
+        let cfg = apply_overrides(toml::Table::new(), &overrides, &mut provenance).unwrap();
+        assert_eq!(cfg.concurrency, 4);
+        assert_eq!(cfg.locales.names(), ["fr-FR", "de-DE"]);
+        assert_eq!(cfg.openai.model, "gpt-4o-mini");
+        assert_eq!(provenance["concurrency"], "env RUSTYLANG_CONCURRENCY");
+        let mut bad = Config {
+            concurrency: 0,
+            locales: vec!["french".into()].into(),
+            ..Config::default()
+        };
+        bad.source_locale = "en-GB".into();
+        let err = bad.validate().unwrap_err().to_string();
+        assert!(
+            err.contains("concurrency") && err.contains("\"french\""),
+            "{}",
+            err
+        );
+    }
+}
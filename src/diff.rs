@@ -1,5 +1,7 @@
-use serde_json::{Value};
-use std::collections::BTreeMap;
+use crate::locales::qualify;
+use crate::patterns::KeyMatcher;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
 
 // Flatten string leaves with dot paths
 pub fn flatten_string_paths(v: &Value, prefix: Option<&str>) -> BTreeMap<String, String> {
@@ -8,39 +10,184 @@ pub fn flatten_string_paths(v: &Value, prefix: Option<&str>) -> BTreeMap<String,
         Value::Object(obj) => {
             for (k, val) in obj.iter() {
                 let seg = escape_key(k);
-                let key = match prefix { Some(p) if !p.is_empty() => format!("{}.{}", p, seg), _ => seg };
+                let key = match prefix {
+                    Some(p) if !p.is_empty() => format!("{}.{}", p, seg),
+                    _ => seg,
+                };
                 map.extend(flatten_string_paths(val, Some(&key)));
             }
         }
         Value::Array(arr) => {
             for (i, val) in arr.iter().enumerate() {
-                let key = match prefix { Some(p) => format!("{}[{}]", p, i), None => format!("[{}]", i) };
+                let key = match prefix {
+                    Some(p) => format!("{}[{}]", p, i),
+                    None => format!("[{}]", i),
+                };
                 map.extend(flatten_string_paths(val, Some(&key)));
             }
         }
         Value::String(s) => {
-            if let Some(p) = prefix { map.insert(p.to_string(), s.to_string()); }
+            if let Some(p) = prefix {
+                map.insert(p.to_string(), s.to_string());
+            }
         }
         _ => {}
     }
     map
 }
 
-fn escape_key(k: &str) -> String { k.replace('.', "\\.") }
+fn escape_key(k: &str) -> String {
+    k.replace('.', "\\.")
+}
+
+// Keys whose source text was added or modified between two versions of the flattened source
+pub fn changed_keys(
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+) -> BTreeSet<String> {
+    new.iter()
+        .filter(|(path, text)| old.get(*path) != Some(*text))
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+// Keys a translate run should request, from flattened source and target: missing or empty
+// ones, or every key with `overwrite`. With `changed`
+// (from `--since`) only those keys are considered, and they are retranslated even when the
+// target already has a value.
+pub fn keys_to_translate(
+    source: &BTreeMap<String, String>,
+    target: &BTreeMap<String, String>,
+    overwrite: bool,
+    changed: Option<&BTreeSet<String>>,
+) -> Vec<(String, String)> {
+    source
+        .iter()
+        .filter(|(path, _)| match changed {
+            Some(keys) => keys.contains(*path),
+            None => overwrite || target.get(*path).is_none_or(|s| s.is_empty()),
+        })
+        .map(|(path, english)| (path.clone(), english.clone()))
+        .collect()
+}
+
+// Remove leaves (strings, numbers, bools, nulls) from target that are not in the source, plus
+// any objects/arrays left empty by that. Paths matching `keep` are never touched. Array elements
+// are only dropped from the end so surviving indices keep lining up with the source.
+// `keep` sees namespace-qualified paths, and so do the returned (path, value) pairs.
+pub fn prune_orphans(
+    target: &mut Value,
+    source: &Value,
+    namespace: Option<&str>,
+    keep: &KeyMatcher,
+) -> Vec<(String, Value)> {
+    let mut src = BTreeSet::new();
+    leaf_paths(source, None, &mut src);
+    let mut removed = Vec::new();
+    prune_node(
+        target,
+        None,
+        &src,
+        &|path| keep.is_match(&qualify(namespace, path)),
+        &mut removed,
+    );
+    removed
+        .into_iter()
+        .map(|(path, value)| (qualify(namespace, &path), value))
+        .collect()
+}
 
-// Compute list of (path, english) to fill on target. If overwrite=true, include all string leaves.
-pub fn compute_missing_translations(source: &Value, target: &Value, overwrite: bool) -> Vec<(String, String)> {
-    let src = flatten_string_paths(source, None);
-    let tgt = flatten_string_paths(target, None);
-    let mut out = Vec::new();
-    for (path, english) in src.into_iter() {
-        if overwrite {
-            out.push((path, english));
-        } else if !tgt.contains_key(&path) || tgt.get(&path).map(|s| s.is_empty()).unwrap_or(true) {
-            out.push((path, english));
+// Paths of every non-container value, named like `flatten_string_paths` does
+fn leaf_paths(v: &Value, prefix: Option<&str>, out: &mut BTreeSet<String>) {
+    match v {
+        Value::Object(obj) => {
+            for (k, val) in obj {
+                let seg = escape_key(k);
+                let key = match prefix {
+                    Some(p) if !p.is_empty() => format!("{}.{}", p, seg),
+                    _ => seg,
+                };
+                leaf_paths(val, Some(&key), out);
+            }
+        }
+        Value::Array(arr) => {
+            for (i, val) in arr.iter().enumerate() {
+                let key = match prefix {
+                    Some(p) => format!("{}[{}]", p, i),
+                    None => format!("[{}]", i),
+                };
+                leaf_paths(val, Some(&key), out);
+            }
+        }
+        _ => {
+            if let Some(p) = prefix {
+                out.insert(p.to_string());
+            }
         }
     }
-    out
+}
+
+// Returns true when the node became empty because of pruning
+fn prune_node(
+    v: &mut Value,
+    prefix: Option<&str>,
+    src: &BTreeSet<String>,
+    keep: &dyn Fn(&str) -> bool,
+    removed: &mut Vec<(String, Value)>,
+) -> bool {
+    let is_orphan = |path: &str, child: &Value| {
+        !child.is_object() && !child.is_array() && !src.contains(path) && !keep(path)
+    };
+    match v {
+        Value::Object(obj) => {
+            if obj.is_empty() {
+                return false;
+            }
+            let keys: Vec<String> = obj.keys().cloned().collect();
+            for k in keys {
+                let seg = escape_key(&k);
+                let path = match prefix {
+                    Some(p) if !p.is_empty() => format!("{}.{}", p, seg),
+                    _ => seg,
+                };
+                let Some(child) = obj.get_mut(&k) else {
+                    continue;
+                };
+                let orphan = is_orphan(&path, child);
+                if (orphan || prune_node(child, Some(&path), src, keep, removed))
+                    && let Some(value) = obj.shift_remove(&k)
+                    && orphan
+                {
+                    removed.push((path, value));
+                }
+            }
+            obj.is_empty()
+        }
+        Value::Array(arr) => {
+            if arr.is_empty() {
+                return false;
+            }
+            let mut trailing = true;
+            for i in (0..arr.len()).rev() {
+                let path = match prefix {
+                    Some(p) => format!("{}[{}]", p, i),
+                    None => format!("[{}]", i),
+                };
+                let drop = is_orphan(&path, &arr[i])
+                    || prune_node(&mut arr[i], Some(&path), src, keep, removed);
+                if drop && trailing {
+                    let value = arr.remove(i);
+                    if !value.is_object() && !value.is_array() {
+                        removed.push((path, value));
+                    }
+                } else {
+                    trailing = false;
+                }
+            }
+            arr.is_empty()
+        }
+        _ => false,
+    }
 }
 
 #[cfg(test)]
@@ -51,7 +198,12 @@ mod tests {
     fn missing_only_when_not_overwrite() {
         let source: Value = serde_json::json!({"a": {"b": "hello"}});
         let target: Value = serde_json::json!({"a": {"b": ""}});
-        let v = compute_missing_translations(&source, &target, false);
+        let v = keys_to_translate(
+            &flatten_string_paths(&source, None),
+            &flatten_string_paths(&target, None),
+            false,
+            None,
+        );
         assert_eq!(v.len(), 1);
         assert_eq!(v[0].0, "a.b");
     }
@@ -60,12 +212,80 @@ mod tests {
     fn all_when_overwrite() {
         let source: Value = serde_json::json!({"a": {"b": "hello"}});
         let target: Value = serde_json::json!({"a": {"b": "world"}});
-        let v = compute_missing_translations(&source, &target, true);
+        let v = keys_to_translate(
+            &flatten_string_paths(&source, None),
+            &flatten_string_paths(&target, None),
+            true,
+            None,
+        );
         assert_eq!(v.len(), 1);
         assert_eq!(v[0].0, "a.b");
         assert_eq!(v[0].1, "hello");
     }
-}
-
 
+    #[test]
+    fn since_selects_added_and_modified_keys_even_if_translated() {
+        let old: Value = serde_json::json!({"a": "same", "b": "old text", "gone": "x"});
+        let new: Value = serde_json::json!({"a": "same", "b": "new text", "c": "added"});
+        let (old, new) = (
+            flatten_string_paths(&old, None),
+            flatten_string_paths(&new, None),
+        );
+        let changed = changed_keys(&old, &new);
+        assert_eq!(
+            changed.iter().map(String::as_str).collect::<Vec<_>>(),
+            ["b", "c"]
+        );
+        let target = flatten_string_paths(
+            &serde_json::json!({"a": "pareil", "b": "ancien texte"}),
+            None,
+        );
+        let v = keys_to_translate(&new, &target, false, Some(&changed));
+        assert_eq!(
+            v,
+            [
+                ("b".to_string(), "new text".to_string()),
+                ("c".to_string(), "added".to_string())
+            ]
+        );
+    }
 
+    #[test]
+    fn prune_removes_orphans_and_empty_parents() {
+        let source: Value = serde_json::json!({"a": {"b": "hello"}, "list": ["x"], "max": 5});
+        let mut target: Value = serde_json::json!({
+            "a": {"b": "bonjour", "old": "vieux"},
+            "gone": {"deep": {"c": "c", "n": null}},
+            "legacy": {"keep": "moi", "on": false},
+            "list": ["x", "y", 2],
+            "count": 3,
+            "flag": true,
+            "max": 5
+        });
+        let keep = KeyMatcher::new(&["app:legacy.**".to_string()]).unwrap();
+        let removed = prune_orphans(&mut target, &source, Some("app"), &keep);
+        let paths: Vec<_> = removed.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "app:a.old",
+                "app:gone.deep.c",
+                "app:gone.deep.n",
+                "app:list[2]",
+                "app:list[1]",
+                "app:count",
+                "app:flag"
+            ]
+        );
+        assert_eq!(removed[5].1, serde_json::json!(3));
+        assert_eq!(
+            target,
+            serde_json::json!({
+                "a": {"b": "bonjour"},
+                "legacy": {"keep": "moi", "on": false},
+                "list": ["x"],
+                "max": 5
+            })
+        );
+    }
+}
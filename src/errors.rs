@@ -6,7 +6,6 @@ pub enum RustyLangError {
     InvalidDotPath(String),
     #[error("Path not found: {0}")]
     PathNotFound(String),
+    #[error("Budget exhausted: {0}")]
+    BudgetExceeded(String),
 }
-
-
-
@@ -0,0 +1,132 @@
+use crate::config::ModelPrice;
+use std::collections::BTreeMap;
+
+// Chat-format overhead: a few tokens per message plus the primed assistant reply
+const TOKENS_PER_MESSAGE: u64 = 4;
+const REPLY_PRIMING_TOKENS: u64 = 3;
+
+// Known list prices in USD per 1M tokens; `[openai.prices]` entries take precedence
+const BUILTIN_PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-5-nano", 0.05, 0.40),
+    ("gpt-5-mini", 0.25, 2.00),
+    ("gpt-5", 1.25, 10.00),
+];
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenEstimate {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl TokenEstimate {
+    pub fn add(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    pub fn cost(&self, price: &ModelPrice) -> f64 {
+        (self.prompt_tokens as f64 * price.input + self.completion_tokens as f64 * price.output)
+            / 1_000_000.0
+    }
+}
+
+// Rough BPE approximation (~4 chars per token for Latin text, ~1 per char for CJK)
+pub fn estimate_tokens(text: &str) -> u64 {
+    let (mut ascii, mut other) = (0u64, 0u64);
+    for c in text.chars() {
+        if c.is_ascii() {
+            ascii += 1;
+        } else {
+            other += 1;
+        }
+    }
+    ascii.div_ceil(4) + other
+}
+
+// Estimate one chat request; the reply is assumed to be about as long as the source text
+pub fn estimate_request(system: &str, user: &str) -> TokenEstimate {
+    let completion = estimate_tokens(user);
+    TokenEstimate {
+        prompt_tokens: estimate_tokens(system)
+            + completion
+            + 2 * TOKENS_PER_MESSAGE
+            + REPLY_PRIMING_TOKENS,
+        completion_tokens: completion,
+    }
+}
+
+// Price for a model: exact config entry, then the longest matching prefix (so dated
+// snapshots like `gpt-4o-mini-2024-07-18` resolve), in config first and then built-ins
+pub fn price_for(model: &str, configured: &BTreeMap<String, ModelPrice>) -> Option<ModelPrice> {
+    if let Some(p) = configured.get(model) {
+        return Some(*p);
+    }
+    let from_config = configured
+        .iter()
+        .filter(|(name, _)| model.starts_with(name.as_str()))
+        .max_by_key(|(name, _)| name.len())
+        .map(|(_, p)| *p);
+    from_config.or_else(|| {
+        BUILTIN_PRICES
+            .iter()
+            .filter(|(name, _, _)| model.starts_with(name))
+            .max_by_key(|(name, _, _)| name.len())
+            .map(|(_, input, output)| ModelPrice {
+                input: *input,
+                output: *output,
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn price_prefers_config_then_longest_builtin_prefix() {
+        let mut configured = BTreeMap::new();
+        assert_eq!(
+            price_for("gpt-4o-mini-2024-07-18", &configured).map(|p| p.input),
+            Some(0.15)
+        );
+        assert!(price_for("unknown-model", &configured).is_none());
+        configured.insert(
+            "gpt-4o".to_string(),
+            ModelPrice {
+                input: 1.0,
+                output: 2.0,
+            },
+        );
+        assert_eq!(
+            price_for("gpt-4o-mini", &configured).map(|p| p.input),
+            Some(1.0)
+        );
+    }
+
+    #[test]
+    fn estimate_counts_prompt_and_completion() {
+        let est = estimate_request("abcdefgh", "abcd");
+        assert_eq!(est.completion_tokens, 1);
+        assert_eq!(
+            est.prompt_tokens,
+            2 + 1 + 2 * TOKENS_PER_MESSAGE + REPLY_PRIMING_TOKENS
+        );
+        assert!(
+            (est.cost(&ModelPrice {
+                input: 1_000_000.0,
+                output: 0.0
+            }) - est.prompt_tokens as f64)
+                .abs()
+                < 1e-9
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::{BTreeMap, HashSet};
+
+// Long strings make poor examples and expensive prompts
+const MAX_EXAMPLE_CHARS: usize = 300;
+
+#[derive(Debug, Clone)]
+struct Example {
+    path: String,
+    source: String,
+    target: String,
+    words: HashSet<String>,
+}
+
+// Existing translations of one target locale, offered to the model as few-shot examples
+#[derive(Debug, Clone, Default)]
+pub struct ExamplePool {
+    examples: Vec<Example>,
+}
+
+impl ExamplePool {
+    // Keys with a translation in `target`. Values equal to the source text (verbatim keys,
+    // `on_failure = "source"` stand-ins) are not translations and are left out, and so are
+    // `excluded` keys: ones being retranslated in this run, or stand-ins whose source has changed
+    // since, would pair a source text with a translation of something else.
+    pub fn new(
+        source: &BTreeMap<String, String>,
+        target: &BTreeMap<String, String>,
+        excluded: impl Fn(&str) -> bool,
+    ) -> Self {
+        let examples = target
+            .iter()
+            .filter(|(path, _)| !excluded(path))
+            .filter_map(|(path, translated)| {
+                let text = source.get(path)?;
+                let usable = !translated.trim().is_empty()
+                    && translated != text
+                    && text.chars().count() <= MAX_EXAMPLE_CHARS;
+                usable.then(|| Example {
+                    path: path.clone(),
+                    source: text.clone(),
+                    target: translated.clone(),
+                    words: words(text),
+                })
+            })
+            .collect();
+        Self { examples }
+    }
+
+    // Up to `k` (source, translation) pairs closest to the key being translated: shared leading
+    // key segments plus word overlap of the source texts. The key itself is never its own example.
+    pub fn most_similar(&self, path: &str, text: &str, k: usize) -> Vec<(String, String)> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let wanted = words(text);
+        let segments = path.split('.').count() as f64;
+        let mut scored: Vec<(f64, &Example)> = self
+            .examples
+            .iter()
+            .filter(|e| e.path != path)
+            .map(|e| {
+                let prefix = shared_prefix(path, &e.path) as f64 / segments;
+                let union = wanted.union(&e.words).count();
+                let overlap = if union == 0 {
+                    0.0
+                } else {
+                    wanted.intersection(&e.words).count() as f64 / union as f64
+                };
+                (prefix + overlap, e)
+            })
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.path.cmp(&b.1.path)));
+        scored
+            .into_iter()
+            .take(k)
+            .map(|(_, e)| (e.source.clone(), e.target.clone()))
+            .collect()
+    }
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn shared_prefix(a: &str, b: &str) -> usize {
+    a.split('.')
+        .zip(b.split('.'))
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_sibling_keys_and_similar_text() {
+        let map = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        let source = map(&[
+            ("cart.title", "Your cart"),
+            ("cart.empty", "Your cart is empty"),
+            ("home.title", "Welcome"),
+            ("brand", "Acme"),
+            ("profile.save", "Save profile"),
+        ]);
+        let target = map(&[
+            ("cart.title", "Ihr Warenkorb"),
+            ("cart.empty", "Ihr Warenkorb ist leer"),
+            ("home.title", "Willkommen"),
+            ("brand", "Acme"),
+            ("profile.save", "Profil speichern"),
+        ]);
+        let pool = ExamplePool::new(&source, &target, |path| path == "profile.save");
+        let found = pool.most_similar("cart.checkout", "Check out your cart", 2);
+        assert_eq!(
+            found[0],
+            ("Your cart".to_string(), "Ihr Warenkorb".to_string())
+        );
+        assert_eq!(found.len(), 2);
+        assert!(
+            pool.most_similar("cart.title", "Your cart", 5)
+                .iter()
+                .all(|(s, _)| s != "Your cart")
+        );
+        assert!(pool.most_similar("misc", "Acme", 5).is_empty());
+        assert!(
+            pool.most_similar("profile.title", "Your profile", 5)
+                .iter()
+                .all(|(_, t)| t != "Profil speichern")
+        );
+    }
+}
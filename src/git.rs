@@ -0,0 +1,121 @@
+use anyhow::{Context, Result, anyhow};
+use std::path::Path;
+use std::process::Command;
+
+// Contents of `path` at `git_ref`, or None if the file did not exist in that commit.
+// Uses the local `git` binary, run from the file's directory.
+pub fn read_file_at_ref(git_ref: &str, path: &Path) -> Result<Option<String>> {
+    let dir = path
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Not a file path: {:?}", path))?;
+    let verify = Command::new("git")
+        .current_dir(dir)
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("{}^{{commit}}", git_ref))
+        .output()
+        .context("Running git (is it installed?)")?;
+    if !verify.status.success() {
+        return Err(anyhow!("Unknown git ref {:?}", git_ref));
+    }
+
+    // `./` makes git resolve the path relative to `dir`, not the repo root
+    let spec = format!("{}:./{}", git_ref, file_name.to_string_lossy());
+    // probed by exit status rather than by git's (possibly localized) error messages
+    let exists = Command::new("git")
+        .current_dir(dir)
+        .args(["cat-file", "-e", &spec])
+        .output()
+        .context("Running git cat-file")?;
+    if !exists.status.success() {
+        return Ok(None);
+    }
+    let out = Command::new("git")
+        .current_dir(dir)
+        .args(["show", &spec])
+        .output()
+        .context("Running git show")?;
+    if out.status.success() {
+        return Ok(Some(
+            String::from_utf8(out.stdout).with_context(|| format!("Decoding {}", spec))?,
+        ));
+    }
+    Err(anyhow!(
+        "git show {} failed: {}",
+        spec,
+        String::from_utf8_lossy(&out.stderr).trim()
+    ))
+}
+
+// Whether `path` is tracked in its git repository; false outside a repository or without git
+pub fn is_tracked(path: &Path) -> bool {
+    let dir = path
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let Some(file_name) = path.file_name() else {
+        return false;
+    };
+    Command::new("git")
+        .current_dir(dir)
+        .args(["ls-files", "--error-unmatch", "--"])
+        .arg(file_name)
+        .output()
+        .is_ok_and(|out| out.status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use std::fs;
+
+    #[test]
+    fn reads_files_at_a_ref_and_reports_missing_ones() {
+        let dir = temp_dir("git");
+        let git = |args: &[&str]| {
+            let out = Command::new("git")
+                .current_dir(&dir)
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(
+                out.status.success(),
+                "git {:?}: {}",
+                args,
+                String::from_utf8_lossy(&out.stderr)
+            );
+        };
+        git(&["init", "-q"]);
+        fs::create_dir(dir.join("i18n")).unwrap();
+        fs::write(dir.join("i18n/en-GB.json"), "{}").unwrap();
+        git(&["add", "."]);
+        git(&[
+            "-c",
+            "user.name=t",
+            "-c",
+            "user.email=t@t",
+            "commit",
+            "-qm",
+            "init",
+        ]);
+        fs::write(dir.join("i18n/fr-FR.json"), "{}").unwrap();
+        assert_eq!(
+            read_file_at_ref("HEAD", &dir.join("i18n/en-GB.json"))
+                .unwrap()
+                .as_deref(),
+            Some("{}")
+        );
+        assert_eq!(
+            read_file_at_ref("HEAD", &dir.join("i18n/fr-FR.json")).unwrap(),
+            None
+        );
+        assert!(read_file_at_ref("no-such-ref", &dir.join("i18n/en-GB.json")).is_err());
+        assert!(
+            is_tracked(&dir.join("i18n/en-GB.json")) && !is_tracked(&dir.join("i18n/fr-FR.json"))
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use crate::config::{Format, Newline, SortKeys};
+use crate::diff::flatten_string_paths;
+use crate::json_utils::read_json_file;
+use crate::locales::is_locale_code;
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SKIP_DIRS: &[&str] = &["node_modules", "target", "dist", "build", "vendor"];
+const MAX_DEPTH: usize = 6;
+// Directory names under which any bare language code (`de`, `fil`) is taken as a locale
+const LOCALE_DIRS: &[&str] = &[
+    "locale",
+    "locales",
+    "i18n",
+    "l10n",
+    "lang",
+    "langs",
+    "languages",
+    "translations",
+    "messages",
+];
+// ISO 639-1 language codes
+const ISO_639_1: &str = "\
+    aa ab ae af ak am an ar as av ay az ba be bg bh bi bm bn bo br bs ca ce ch co cr cs cu cv \
+    cy da de dv dz ee el en eo es et eu fa ff fi fj fo fr fy ga gd gl gn gu gv ha he hi ho hr \
+    ht hu hy hz ia id ie ig ii ik io is it iu ja jv ka kg ki kj kk kl km kn ko kr ks ku kv kw \
+    ky la lb lg li ln lo lt lu lv mg mh mi mk ml mn mr ms mt my na nb nd ne ng nl nn no nr nv \
+    ny oc oj om or os pa pi pl ps pt qu rm rn ro ru rw sa sc sd se sg si sk sl sm sn so sq sr \
+    ss st su sv sw ta te tg th ti tk tl tn to tr ts tt tw ty ug uk ur uz ve vi vo wa wo xh yi \
+    yo za zh zu";
+
+// A locale file layout found on disk by `rustylang init`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub file_pattern: String,
+    /// Locales with at least one file, sorted
+    pub locales: Vec<String>,
+}
+
+// Every `.json` file under `dir` (relative paths), skipping hidden and build directories
+pub fn scan_json_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    scan(dir, Path::new(""), 0, &mut out)?;
+    out.sort();
+    Ok(out)
+}
+
+fn scan(root: &Path, rel: &Path, depth: usize, out: &mut Vec<PathBuf>) -> Result<()> {
+    if depth > MAX_DEPTH {
+        return Ok(());
+    }
+    for entry in fs::read_dir(root.join(rel))? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = rel.join(&name);
+        if entry.file_type()?.is_dir() {
+            if name.starts_with('.') || SKIP_DIRS.contains(&name.as_str()) {
+                continue;
+            }
+            scan(root, &path, depth + 1, out)?;
+        } else if name.ends_with(".json") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Candidate layouts for a list of JSON files, most locales first. A locale is recognised
+// as the file stem (`fr-FR.json`), its last dotted part (`messages.fr-FR.json`) or a
+// directory (`locales/fr-FR/common.json`); several file names under locale directories
+// become `{namespace}`. Bare language codes like `de` must be ISO 639-1 codes or sit under a
+// directory such as `locales/`, and outside such a directory only count when at least two
+// locales share the layout, so `app.json` next to `api.json` is not mistaken for one.
+pub fn infer_layouts(files: &[PathBuf]) -> Vec<Layout> {
+    // pattern -> locale -> file names (for locale directories)
+    let mut found: BTreeMap<String, BTreeMap<String, BTreeSet<String>>> = BTreeMap::new();
+    for file in files {
+        let parts: Vec<String> = file
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        let Some((name, dirs)) = parts.split_last() else {
+            continue;
+        };
+        let stem = name.trim_end_matches(".json");
+        let (prefix, last) = match stem.rsplit_once('.') {
+            Some((prefix, last)) => (format!("{}.", prefix), last),
+            None => (String::new(), stem),
+        };
+        if plausible_locale(last, dirs) {
+            let pattern = join(dirs, &format!("{}{{locale}}.json", prefix));
+            found
+                .entry(pattern)
+                .or_default()
+                .entry(last.to_string())
+                .or_default();
+        }
+        if let Some(i) = (0..dirs.len())
+            .rev()
+            .find(|&i| plausible_locale(&dirs[i], &dirs[..i]))
+        {
+            let mut dirs = dirs.to_vec();
+            let locale = std::mem::replace(&mut dirs[i], "{locale}".to_string());
+            let pattern = join(&dirs, "*");
+            found
+                .entry(pattern)
+                .or_default()
+                .entry(locale)
+                .or_default()
+                .insert(name.clone());
+        }
+    }
+
+    let mut layouts: Vec<Layout> = found
+        .into_iter()
+        .filter(|(pattern, locales)| {
+            locales.len() >= 2
+                || locales.keys().any(|l| l.contains(['-', '_']))
+                || pattern
+                    .split('/')
+                    .any(|d| LOCALE_DIRS.contains(&d.to_lowercase().as_str()))
+        })
+        .map(|(pattern, locales)| {
+            let names: BTreeSet<&String> = locales.values().flatten().collect();
+            let file_pattern = match pattern.strip_suffix('*') {
+                Some(dir) if names.len() == 1 => format!("{}{}", dir, names.iter().next().unwrap()),
+                Some(dir) => format!("{}{{namespace}}.json", dir),
+                None => pattern,
+            };
+            Layout {
+                file_pattern,
+                locales: locales.into_keys().collect(),
+            }
+        })
+        .collect();
+    layouts.sort_by(|a, b| {
+        b.locales
+            .len()
+            .cmp(&a.locales.len())
+            .then(a.file_pattern.cmp(&b.file_pattern))
+    });
+    layouts
+}
+
+// A tag with a region or script, or a bare language code that is a known one or sits in a locale
+// directory
+fn plausible_locale(code: &str, parents: &[String]) -> bool {
+    if !is_locale_code(code) {
+        return false;
+    }
+    code.contains(['-', '_'])
+        || ISO_639_1
+            .split_whitespace()
+            .any(|c| c.eq_ignore_ascii_case(code))
+        || parents
+            .iter()
+            .any(|d| LOCALE_DIRS.contains(&d.to_lowercase().as_str()))
+}
+
+fn join(dirs: &[String], file: &str) -> String {
+    let mut parts = dirs.to_vec();
+    parts.push(file.to_string());
+    parts.join("/")
+}
+
+// The source is usually the most complete locale; English breaks ties
+pub fn guess_source_locale(counts: &BTreeMap<String, usize>) -> Option<String> {
+    counts
+        .iter()
+        .max_by_key(|(locale, count)| {
+            (
+                **count,
+                locale.starts_with("en"),
+                std::cmp::Reverse(locale.len()),
+            )
+        })
+        .map(|(locale, _)| locale.clone())
+}
+
+pub fn count_strings(files: &[PathBuf]) -> usize {
+    files
+        .iter()
+        .filter_map(|f| read_json_file(f).ok())
+        .map(|v| flatten_string_paths(&v, None).len())
+        .sum()
+}
+
+// Formatting settings that reproduce an existing file as closely as possible
+pub fn detect_format(text: &str) -> Format {
+    let indent = text
+        .lines()
+        .map(|l| l.len() - l.trim_start_matches(' ').len())
+        .find(|n| *n > 0)
+        .unwrap_or(2);
+    let sorted = serde_json::from_str::<Value>(text).is_ok_and(|v| keys_sorted(&v));
+    Format {
+        indent,
+        sort_keys: if sorted {
+            SortKeys::Alphabetical
+        } else {
+            SortKeys::None
+        },
+        escape_unicode: text.contains("\\u") && text.is_ascii(),
+        newline: if text.contains("\r\n") {
+            Newline::Crlf
+        } else {
+            Newline::Lf
+        },
+        final_newline: text.ends_with('\n'),
+    }
+}
+
+fn keys_sorted(v: &Value) -> bool {
+    match v {
+        Value::Object(obj) => obj.keys().is_sorted() && obj.values().all(keys_sorted),
+        Value::Array(arr) => arr.iter().all(keys_sorted),
+        _ => true,
+    }
+}
+
+// rustylang.toml contents for the chosen settings, commented like the README example
+pub fn render_config(
+    file_pattern: &str,
+    source_locale: &str,
+    locales: &[String],
+    format: &Format,
+) -> String {
+    let list: Vec<String> = locales.iter().map(|l| format!("{:?}", l)).collect();
+    format!(
+        concat!(
+            "# Generated by `rustylang init`; see the README for every setting\n",
+            "source_locale = {:?}\n",
+            "file_pattern = {:?}\n",
+            "locales = [{}]\n",
+            "\n",
+            "[openai]\n",
+            "# api_key is read from OPENAI_API_KEY; or set api_key_command / api_key_file\n",
+            "model = {:?}\n",
+            "\n",
+            "[format]\n",
+            "indent = {}\n",
+            "sort_keys = {:?}\n",
+            "escape_unicode = {}\n",
+            "newline = {:?}\n",
+            "final_newline = {}\n",
+        ),
+        source_locale,
+        file_pattern,
+        list.join(", "),
+        crate::config::OpenAi::default().model,
+        format.indent,
+        match format.sort_keys {
+            SortKeys::None => "none",
+            SortKeys::Alphabetical => "alphabetical",
+            SortKeys::Source => "source",
+        },
+        format.escape_unicode,
+        match format.newline {
+            Newline::Lf => "lf",
+            Newline::Crlf => "crlf",
+        },
+        format.final_newline,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn infers_flat_and_namespaced_layouts() {
+        let files: Vec<PathBuf> = [
+            "package.json",
+            "app.json",
+            "api.json",
+            "src/lang/fil.json",
+            "src/lang/haw.json",
+            "src/i18n/en-GB.json",
+            "src/i18n/fr-FR.json",
+            "public/locales/en/common.json",
+            "public/locales/en/checkout.json",
+            "public/locales/de/common.json",
+            "public/locales/de/checkout.json",
+            "public/locales/nl/common.json",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        let layouts = infer_layouts(&files);
+        assert_eq!(
+            layouts[0].file_pattern,
+            "public/locales/{locale}/{namespace}.json"
+        );
+        assert_eq!(layouts[0].locales, ["de", "en", "nl"]);
+        assert_eq!(
+            layouts[1],
+            Layout {
+                file_pattern: "src/i18n/{locale}.json".into(),
+                locales: vec!["en-GB".into(), "fr-FR".into()]
+            }
+        );
+        assert_eq!(
+            layouts[2],
+            Layout {
+                file_pattern: "src/lang/{locale}.json".into(),
+                locales: vec!["fil".into(), "haw".into()]
+            }
+        );
+        assert_eq!(layouts.len(), 3);
+        let lone = infer_layouts(
+            &["app.json", "api.json", "public/locales/en/common.json"].map(PathBuf::from),
+        );
+        assert_eq!(
+            lone,
+            [Layout {
+                file_pattern: "public/locales/{locale}/common.json".into(),
+                locales: vec!["en".into()]
+            }]
+        );
+    }
+
+    #[test]
+    fn detects_existing_formatting() {
+        let f = detect_format("{\r\n    \"b\": \"x\",\r\n    \"a\": \"y\"\r\n}");
+        assert_eq!(
+            (f.indent, f.sort_keys, f.newline, f.final_newline),
+            (4, SortKeys::None, Newline::Crlf, false)
+        );
+    }
+}
@@ -1,5 +1,8 @@
+use crate::config::{Format, Newline, SortKeys};
 use crate::errors::RustyLangError;
 use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::ser::{PrettyFormatter, Serializer};
 use serde_json::{Map, Value};
 use std::fs;
 use std::path::Path;
@@ -13,8 +16,82 @@ pub fn read_json_file(path: &Path) -> Result<Value> {
     Ok(v)
 }
 
-pub fn write_json_atomic(path: &Path, json: &Value) -> Result<()> {
-    let pretty = serde_json::to_string_pretty(json)?;
+// Serialise a value using the configured indent, escaping and line endings
+pub fn render_json(json: &Value, format: &Format) -> Result<String> {
+    let indent = " ".repeat(format.indent);
+    let mut buf = Vec::new();
+    let mut ser =
+        Serializer::with_formatter(&mut buf, PrettyFormatter::with_indent(indent.as_bytes()));
+    json.serialize(&mut ser)?;
+    let mut out = String::from_utf8(buf)?;
+    if format.escape_unicode {
+        out = escape_non_ascii(&out);
+    }
+    if format.final_newline {
+        out.push('\n');
+    }
+    // JSON strings never contain raw newlines, so every '\n' here comes from the formatter
+    if format.newline == Newline::Crlf {
+        out = out.replace('\n', "\r\n");
+    }
+    Ok(out)
+}
+
+// Structural JSON is pure ASCII, so any non-ASCII char is inside a string literal
+fn escape_non_ascii(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c.is_ascii() {
+            out.push(c);
+        } else {
+            let mut units = [0u16; 2];
+            for u in c.encode_utf16(&mut units) {
+                out.push_str(&format!("\\u{:04x}", u));
+            }
+        }
+    }
+    out
+}
+
+// Reorder object keys in place. `Source` mirrors `reference` (keys it doesn't know go last);
+// without a reference it leaves the order untouched.
+pub fn apply_key_order(json: &mut Value, order: SortKeys, reference: Option<&Value>) {
+    match json {
+        Value::Object(map) => {
+            match order {
+                SortKeys::None => {}
+                SortKeys::Alphabetical => map.sort_keys(),
+                SortKeys::Source => {
+                    if let Some(Value::Object(ref_map)) = reference {
+                        let mut ordered = Map::new();
+                        for k in ref_map.keys() {
+                            if let Some(v) = map.shift_remove(k) {
+                                ordered.insert(k.clone(), v);
+                            }
+                        }
+                        ordered.append(map);
+                        *map = ordered;
+                    }
+                }
+            }
+            for (k, v) in map.iter_mut() {
+                apply_key_order(v, order, reference.and_then(|r| r.get(k)));
+            }
+        }
+        Value::Array(arr) => {
+            for (i, v) in arr.iter_mut().enumerate() {
+                apply_key_order(v, order, reference.and_then(|r| r.get(i)));
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn write_json_atomic(path: &Path, json: &Value, format: &Format) -> Result<()> {
+    let pretty = render_json(json, format)?;
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).with_context(|| format!("Creating {:?}", dir))?;
+    }
     let tmp_path = path.with_extension("tmp");
     // backup
     let bak_path = path.with_extension("bak");
@@ -40,31 +117,52 @@ fn parse_dot_path(path: &str) -> Result<Vec<PathSegment>> {
     while let Some(c) = chars.next() {
         match c {
             '\\' => {
-                if let Some(next) = chars.next() { buf.push(next); } else { buf.push('\\'); }
+                if let Some(next) = chars.next() {
+                    buf.push(next);
+                } else {
+                    buf.push('\\');
+                }
             }
             '.' => {
-                if !buf.is_empty() { segments.push(PathSegment::Key(buf.clone())); buf.clear(); }
+                if !buf.is_empty() {
+                    segments.push(PathSegment::Key(buf.clone()));
+                    buf.clear();
+                }
             }
             '[' => {
                 // flush key buffer if present
-                if !buf.is_empty() { segments.push(PathSegment::Key(buf.clone())); buf.clear(); }
+                if !buf.is_empty() {
+                    segments.push(PathSegment::Key(buf.clone()));
+                    buf.clear();
+                }
                 // parse number until ']'
                 let mut num = String::new();
-                while let Some(nc) = chars.next() {
-                    if nc == ']' { break; }
+                for nc in chars.by_ref() {
+                    if nc == ']' {
+                        break;
+                    }
                     num.push(nc);
                 }
-                let idx: usize = num.parse().map_err(|_| RustyLangError::InvalidDotPath(path.to_string()))?;
+                let idx: usize = num
+                    .parse()
+                    .map_err(|_| RustyLangError::InvalidDotPath(path.to_string()))?;
                 segments.push(PathSegment::Index(idx));
             }
             _ => buf.push(c),
         }
     }
-    if !buf.is_empty() { segments.push(PathSegment::Key(buf)); }
+    if !buf.is_empty() {
+        segments.push(PathSegment::Key(buf));
+    }
     Ok(segments)
 }
 
-pub fn set_value_at_path(root: &mut Value, path: &str, value: Value, create_missing: bool) -> Result<()> {
+pub fn set_value_at_path(
+    root: &mut Value,
+    path: &str,
+    value: Value,
+    create_missing: bool,
+) -> Result<()> {
     let segments = parse_dot_path(path)?;
     let mut current = root;
     for (i, seg) in segments.iter().enumerate() {
@@ -73,14 +171,19 @@ pub fn set_value_at_path(root: &mut Value, path: &str, value: Value, create_miss
             PathSegment::Key(k) => {
                 if is_last {
                     ensure_object(current)?;
-                    if let Value::Object(map) = current { map.insert(k.clone(), value); }
+                    if let Value::Object(map) = current {
+                        map.insert(k.clone(), value);
+                    }
                     return Ok(());
                 }
                 match current {
                     Value::Object(map) => {
                         if !map.contains_key(k) {
-                            if create_missing { map.insert(k.clone(), Value::Object(Map::new())); }
-                            else { return Err(RustyLangError::PathNotFound(path.to_string()).into()); }
+                            if create_missing {
+                                map.insert(k.clone(), Value::Object(Map::new()));
+                            } else {
+                                return Err(RustyLangError::PathNotFound(path.to_string()).into());
+                            }
                         }
                         current = map.get_mut(k).unwrap();
                     }
@@ -91,21 +194,31 @@ pub fn set_value_at_path(root: &mut Value, path: &str, value: Value, create_miss
                 // arrays: convert current to array if create_missing
                 if is_last {
                     ensure_array(current, *idx, create_missing)?;
-                    if let Value::Array(arr) = current { if *idx >= arr.len() { arr.resize(*idx + 1, Value::Null); } arr[*idx] = value; }
+                    if let Value::Array(arr) = current {
+                        if *idx >= arr.len() {
+                            arr.resize(*idx + 1, Value::Null);
+                        }
+                        arr[*idx] = value;
+                    }
                     return Ok(());
                 }
                 match current {
                     Value::Array(arr) => {
                         if *idx >= arr.len() {
-                            if create_missing { arr.resize(*idx + 1, Value::Object(Map::new())); }
-                            else { return Err(RustyLangError::PathNotFound(path.to_string()).into()); }
+                            if create_missing {
+                                arr.resize(*idx + 1, Value::Object(Map::new()));
+                            } else {
+                                return Err(RustyLangError::PathNotFound(path.to_string()).into());
+                            }
                         }
                         current = &mut arr[*idx];
                     }
                     Value::Null if create_missing => {
                         *current = Value::Array(vec![]);
                         ensure_array(current, *idx, true)?;
-                        if let Value::Array(arr) = current { current = &mut arr[*idx]; }
+                        if let Value::Array(arr) = current {
+                            current = &mut arr[*idx];
+                        }
                     }
                     _ => return Err(RustyLangError::InvalidDotPath(path.to_string()).into()),
                 }
@@ -116,15 +229,22 @@ pub fn set_value_at_path(root: &mut Value, path: &str, value: Value, create_miss
 }
 
 fn ensure_object(v: &mut Value) -> Result<()> {
-    if matches!(v, Value::Object(_)) { return Ok(()); }
-    if v.is_null() { *v = Value::Object(Map::new()); return Ok(()); }
+    if matches!(v, Value::Object(_)) {
+        return Ok(());
+    }
+    if v.is_null() {
+        *v = Value::Object(Map::new());
+        return Ok(());
+    }
     Err(RustyLangError::InvalidDotPath("expected object".into()).into())
 }
 
 fn ensure_array(v: &mut Value, min_index: usize, create_missing: bool) -> Result<()> {
     match v {
         Value::Array(arr) => {
-            if arr.len() <= min_index { arr.resize(min_index + 1, Value::Null); }
+            if arr.len() <= min_index {
+                arr.resize(min_index + 1, Value::Null);
+            }
             Ok(())
         }
         Value::Null if create_missing => {
@@ -153,6 +273,31 @@ mod tests {
         assert!(matches!(&segs[0], PathSegment::Key(k) if k == "labels"));
         assert!(matches!(&segs[1], PathSegment::Key(k) if k == "some.key"));
     }
-}
 
+    #[test]
+    fn render_json_honours_format_options() {
+        let json = serde_json::json!({"a": "é"});
+        let format = Format {
+            indent: 4,
+            escape_unicode: true,
+            newline: Newline::Crlf,
+            final_newline: true,
+            ..Format::default()
+        };
+        assert_eq!(
+            render_json(&json, &format).unwrap(),
+            "{\r\n    \"a\": \"\\u00e9\"\r\n}\r\n"
+        );
+    }
 
+    #[test]
+    fn apply_key_order_mirrors_source() {
+        let source = serde_json::json!({"b": {"y": "", "x": ""}, "a": ""});
+        let mut target = serde_json::json!({"extra": "", "a": "", "b": {"x": "", "y": ""}});
+        apply_key_order(&mut target, SortKeys::Source, Some(&source));
+        let keys: Vec<_> = target.as_object().unwrap().keys().cloned().collect();
+        assert_eq!(keys, ["b", "a", "extra"]);
+        let inner: Vec<_> = target["b"].as_object().unwrap().keys().cloned().collect();
+        assert_eq!(inner, ["y", "x"]);
+    }
+}
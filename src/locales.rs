@@ -0,0 +1,270 @@
+use crate::diff::flatten_string_paths;
+use crate::json_utils::{read_json_file, set_value_at_path};
+use anyhow::{Context, Result, anyhow};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+const LOCALE_VAR: &str = "{locale}";
+const NAMESPACE_VAR: &str = "{namespace}";
+
+// One file of a locale: the whole locale, or a single namespace of it when the
+// pattern contains `{namespace}`
+#[derive(Debug, Clone)]
+pub struct LocaleFile {
+    pub namespace: Option<String>,
+    pub path: PathBuf,
+    pub json: Value,
+}
+
+// Resolve the file for a locale from a pattern such as `{locale}.json`
+pub fn locale_path(file_pattern: &str, locale: &str) -> PathBuf {
+    PathBuf::from(file_pattern.replace(LOCALE_VAR, locale))
+}
+
+// Resolve one namespace file from a pattern such as `locales/{locale}/{namespace}.json`
+pub fn namespace_path(file_pattern: &str, locale: &str, namespace: Option<&str>) -> PathBuf {
+    match namespace {
+        Some(ns) => locale_path(&file_pattern.replace(NAMESPACE_VAR, ns), locale),
+        None => locale_path(file_pattern, locale),
+    }
+}
+
+pub fn is_namespaced(file_pattern: &str) -> bool {
+    file_pattern.contains(NAMESPACE_VAR)
+}
+
+// Keys are written `namespace:dot.path` in namespaced layouts
+pub fn qualify(namespace: Option<&str>, path: &str) -> String {
+    match namespace {
+        Some(ns) => format!("{}:{}", ns, path),
+        None => path.to_string(),
+    }
+}
+
+// BCP 47 language tag: language, optional script, region and variants. `_` is accepted as a
+// separator too since files are often named `pt_BR.json`.
+pub fn is_locale_code(s: &str) -> bool {
+    let re = Regex::new(concat!(
+        r"^[A-Za-z]{2,3}(?:[-_][A-Za-z]{4})?(?:[-_](?:[A-Za-z]{2}|[0-9]{3}))?",
+        r"(?:[-_](?:[A-Za-z0-9]{5,8}|[0-9][A-Za-z0-9]{3}))*$"
+    ))
+    .unwrap();
+    re.is_match(s)
+}
+
+// Find every locale that has a file on disk matching the pattern, sorted
+pub fn discover_locales(file_pattern: &str) -> Result<Vec<String>> {
+    discover(file_pattern, "locale")
+}
+
+// Namespaces that exist for `locale` (normally the source locale), sorted.
+// A pattern without `{namespace}` has the single unnamed namespace.
+pub fn discover_namespaces(file_pattern: &str, locale: &str) -> Result<Vec<Option<String>>> {
+    if !is_namespaced(file_pattern) {
+        return Ok(vec![None]);
+    }
+    let namespaces = discover(&file_pattern.replace(LOCALE_VAR, locale), "namespace")?;
+    Ok(namespaces.into_iter().map(Some).collect())
+}
+
+// Read the given namespaces of a locale; missing files come back as empty objects
+pub fn read_locale(
+    file_pattern: &str,
+    locale: &str,
+    namespaces: &[Option<String>],
+) -> Result<Vec<LocaleFile>> {
+    namespaces
+        .iter()
+        .map(|ns| {
+            let path = namespace_path(file_pattern, locale, ns.as_deref());
+            let json = read_json_file(&path).with_context(|| format!("Reading {:?}", path))?;
+            Ok(LocaleFile {
+                namespace: ns.clone(),
+                path,
+                json,
+            })
+        })
+        .collect()
+}
+
+// String leaves of every file, keyed by namespace-qualified dot path
+pub fn flatten_locale(files: &[LocaleFile]) -> BTreeMap<String, String> {
+    let mut map = BTreeMap::new();
+    for file in files {
+        for (path, text) in flatten_string_paths(&file.json, None) {
+            map.insert(qualify(file.namespace.as_deref(), &path), text);
+        }
+    }
+    map
+}
+
+// Set a (namespace-qualified) key in whichever file holds its namespace
+pub fn set_locale_value(
+    files: &mut [LocaleFile],
+    key: &str,
+    value: Value,
+    create_missing: bool,
+) -> Result<()> {
+    let (file, path) = match files {
+        [file] if file.namespace.is_none() => (file, key),
+        _ => {
+            let (ns, path) = key
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Key {:?} has no namespace", key))?;
+            let file = files
+                .iter_mut()
+                .find(|f| f.namespace.as_deref() == Some(ns))
+                .ok_or_else(|| anyhow!("Unknown namespace {:?} in key {:?}", ns, key))?;
+            (file, path)
+        }
+    };
+    set_value_at_path(&mut file.json, path, value, create_missing)
+}
+
+// Values of the variable `var` for which a matching file exists. Other variables in the
+// pattern match anything, but must take the same value wherever they repeat.
+fn discover(file_pattern: &str, var: &str) -> Result<Vec<String>> {
+    let pattern = Path::new(file_pattern);
+    let mut found = BTreeSet::new();
+    walk(
+        Path::new("."),
+        &pattern.components().collect::<Vec<_>>(),
+        var,
+        &BTreeMap::new(),
+        &mut found,
+    )?;
+    Ok(found.into_iter().collect())
+}
+
+fn walk(
+    dir: &Path,
+    rest: &[Component],
+    var: &str,
+    captured: &BTreeMap<String, String>,
+    found: &mut BTreeSet<String>,
+) -> Result<()> {
+    let Some((head, tail)) = rest.split_first() else {
+        if let Some(v) = captured.get(var) {
+            found.insert(v.clone());
+        }
+        return Ok(());
+    };
+    let seg = head.as_os_str().to_string_lossy();
+    if !seg.contains(LOCALE_VAR) && !seg.contains(NAMESPACE_VAR) {
+        let next = dir.join(head);
+        if tail.is_empty() && !next.is_file() {
+            return Ok(());
+        }
+        if !tail.is_empty() && !next.is_dir() {
+            return Ok(());
+        }
+        return walk(&next, tail, var, captured, found);
+    }
+
+    // A segment containing variables: list the directory and capture their values
+    let (re, names) = segment_regex(&seg)?;
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    let entries = fs::read_dir(dir).with_context(|| format!("Listing {:?}", dir))?;
+    'entries: for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(caps) = re.captures(&name) else {
+            continue;
+        };
+        let mut next = captured.clone();
+        for (i, var_name) in names.iter().enumerate() {
+            let value = &caps[i + 1];
+            // the same variable must have one value across the whole path
+            if next.get(var_name).is_some_and(|v| v != value) {
+                continue 'entries;
+            }
+            next.insert(var_name.clone(), value.to_string());
+        }
+        let path = entry.path();
+        if tail.is_empty() && !path.is_file() {
+            continue;
+        }
+        walk(&path, tail, var, &next, found)?;
+    }
+    Ok(())
+}
+
+// Regex for one path segment, with a capture group per variable (names returned in order)
+fn segment_regex(seg: &str) -> Result<(Regex, Vec<String>)> {
+    let var_re = Regex::new(r"\{(locale|namespace)\}")?;
+    let mut re = String::from("^");
+    let mut names = Vec::new();
+    let mut last = 0;
+    for caps in var_re.captures_iter(seg) {
+        let m = caps.get(0).unwrap();
+        re.push_str(&regex::escape(&seg[last..m.start()]));
+        re.push_str(match &caps[1] {
+            // BCP 47-ish so unrelated files like `package.json` are not mistaken for locales
+            "locale" => "([A-Za-z]{2,3}(?:[-_][A-Za-z0-9]{2,8})*)",
+            _ => "(.+?)",
+        });
+        names.push(caps[1].to_string());
+        last = m.end();
+    }
+    re.push_str(&regex::escape(&seg[last..]));
+    re.push('$');
+    Ok((Regex::new(&re)?, names))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segment_regex_captures_locale() {
+        let (re, _) = segment_regex("messages.{locale}.json").unwrap();
+        assert_eq!(&re.captures("messages.fr-FR.json").unwrap()[1], "fr-FR");
+        assert!(re.captures("messages.fr-FR.json.bak").is_none());
+        assert!(
+            segment_regex("{locale}.json")
+                .unwrap()
+                .0
+                .captures("package.json")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn segment_regex_captures_namespace_and_locale() {
+        let (re, names) = segment_regex("{namespace}.{locale}.json").unwrap();
+        assert_eq!(names, ["namespace", "locale"]);
+        let caps = re.captures("checkout.fr-FR.json").unwrap();
+        assert_eq!((&caps[1], &caps[2]), ("checkout", "fr-FR"));
+    }
+
+    #[test]
+    fn set_locale_value_routes_by_namespace() {
+        let file = |ns: &str| LocaleFile {
+            namespace: Some(ns.to_string()),
+            path: PathBuf::new(),
+            json: serde_json::json!({}),
+        };
+        let mut files = vec![file("common"), file("checkout")];
+        set_locale_value(
+            &mut files,
+            "checkout:summary.title",
+            Value::String("Total".into()),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            files[1].json,
+            serde_json::json!({"summary": {"title": "Total"}})
+        );
+        assert_eq!(
+            flatten_locale(&files).keys().collect::<Vec<_>>(),
+            ["checkout:summary.title"]
+        );
+        assert!(set_locale_value(&mut files, "summary.title", Value::Null, true).is_err());
+    }
+}
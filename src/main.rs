@@ -1,9 +1,25 @@
+mod checkpoint;
 mod cli;
 mod config;
 mod diff;
 mod errors;
+mod estimate;
+mod examples;
+mod git;
+mod init;
 mod json_utils;
+mod locales;
 mod openai_client;
+mod patterns;
+mod prompt;
+mod rate_limit;
+mod report;
+mod secrets;
+mod state;
+mod stats;
+#[cfg(test)]
+mod test_support;
+mod translate;
 
 use anyhow::Result;
 use cli::{Cli, Commands};
@@ -15,15 +31,30 @@ async fn main() -> Result<()> {
     let _ = dotenvy::dotenv();
     // init logging
     tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+        )
         .with_target(false)
         .compact()
+        .with_writer(|| secrets::RedactingWriter)
         .init();
 
     let cli = <Cli as clap::Parser>::parse();
 
-    match cli.command {
-        Commands::Set(args) => cli::handle_set(args).await,
-        Commands::Translate(args) => cli::handle_translate(args).await,
+    let result = match cli.command {
+        Commands::Set(args) => cli::handle_set(args, &cli.global).await,
+        Commands::Translate(args) => cli::handle_translate(args, &cli.global).await,
+        Commands::Fmt(args) => cli::handle_fmt(args, &cli.global).await,
+        Commands::Prune(args) => cli::handle_prune(args, &cli.global).await,
+        Commands::Stats(args) => cli::handle_stats(args, &cli.global).await,
+        Commands::Config(args) => cli::handle_config(args, &cli.global).await,
+        Commands::Prompt(args) => cli::handle_prompt(args, &cli.global).await,
+        Commands::Init(args) => cli::handle_init(args, &cli.global).await,
+    };
+    // Same output as returning the error from main, minus any key it mentions
+    if let Err(err) = result {
+        eprintln!("Error: {}", secrets::redact(&format!("{:?}", err)));
+        std::process::exit(1);
     }
+    Ok(())
 }
@@ -0,0 +1,98 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+
+// Matches dot paths against a list of patterns. Patterns are globs by default
+// (`*` within one segment, `**` across segments, `?` one char); prefix with `re:` for a regex.
+#[derive(Debug, Clone, Default)]
+pub struct KeyMatcher {
+    regexes: Vec<Regex>,
+}
+
+impl KeyMatcher {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let regexes = patterns
+            .iter()
+            .map(|p| {
+                let re = match p.strip_prefix("re:") {
+                    Some(r) => r.to_string(),
+                    None => glob_to_regex(p),
+                };
+                Regex::new(&re).with_context(|| format!("Invalid key pattern {:?}", p))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { regexes })
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        self.regexes.iter().any(|re| re.is_match(path))
+    }
+}
+
+// Include/exclude filter over dot paths. No include patterns means every key is included;
+// exclude always wins.
+#[derive(Debug, Clone, Default)]
+pub struct KeyFilter {
+    include: Option<KeyMatcher>,
+    exclude: KeyMatcher,
+}
+
+impl KeyFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        let include = if include.is_empty() {
+            None
+        } else {
+            Some(KeyMatcher::new(include)?)
+        };
+        Ok(Self {
+            include,
+            exclude: KeyMatcher::new(exclude)?,
+        })
+    }
+
+    pub fn allows(&self, path: &str) -> bool {
+        self.include.as_ref().is_none_or(|m| m.is_match(path)) && !self.exclude.is_match(path)
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut re = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                re.push_str(".*");
+            }
+            '*' => re.push_str("[^.]*"),
+            '?' => re.push_str("[^.]"),
+            _ => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    re
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_star_stays_within_segment() {
+        let m = KeyMatcher::new(&["checkout.*".to_string()]).unwrap();
+        assert!(m.is_match("checkout.title"));
+        assert!(!m.is_match("checkout.summary.title"));
+        let m = KeyMatcher::new(&["checkout.**".to_string(), "re:^legal\\.".to_string()]).unwrap();
+        assert!(m.is_match("checkout.summary.title"));
+        assert!(m.is_match("legal.terms"));
+        assert!(!m.is_match("checkoutx.title"));
+    }
+
+    #[test]
+    fn filter_exclude_wins_over_include() {
+        let f = KeyFilter::new(&["checkout.**".to_string()], &["*.debug.*".to_string()]).unwrap();
+        assert!(f.allows("checkout.summary.title"));
+        assert!(!f.allows("checkout.debug.id"));
+        assert!(!f.allows("home.title"));
+        assert!(KeyFilter::default().allows("anything"));
+    }
+}
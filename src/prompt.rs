@@ -0,0 +1,293 @@
+use crate::config::{Config, Formality, LocaleSettings};
+use anyhow::{Context, Result, anyhow};
+use regex::{Captures, Regex};
+use std::fs;
+
+// Variables available in prompt templates as `{{name}}`
+pub const VARIABLES: &[&str] = &[
+    "source_locale",
+    "target_locale",
+    "key",
+    "placeholders",
+    "glossary",
+    "formality",
+    "notes",
+    "style_guide",
+    "examples",
+];
+
+// Built-in template for translation requests. A line is left out when any variable on it is empty.
+const TRANSLATE_TEMPLATE: &str = "\
+You are a professional localization engine.
+- Translate from {{source_locale}} to {{target_locale}}.
+- Preserve placeholders unchanged (verbatim), e.g. {like_this}, :named, %s, {...}, {...}.
+- Do NOT echo instructions or placeholder lists.
+- Required placeholders and terms (must appear verbatim): {{placeholders}}
+- Use these translations for terms: {{glossary}}
+- {{formality}}
+- Style notes for {{target_locale}}: {{notes}}
+- Project style guide: {{style_guide}}
+- Existing translations to stay consistent with: {{examples}}
+- Key (context only; do not output. Only use for context and if you are unsure about the \
+translation): {{key}}
+";
+
+// Built-in template for `fallback_mode = "adapt"`; `source_locale` is the parent locale here
+const ADAPT_TEMPLATE: &str = "\
+You are a professional localization engine.
+- Adapt this {{source_locale}} text for {{target_locale}}: change only regional spelling, \
+vocabulary and conventions.
+- If nothing needs to change, return the text exactly as given.
+- Preserve placeholders unchanged (verbatim).
+- Required placeholders and terms (must appear verbatim): {{placeholders}}
+- Use these translations for terms: {{glossary}}
+- {{formality}}
+- Style notes for {{target_locale}}: {{notes}}
+- Project style guide: {{style_guide}}
+- Existing translations to stay consistent with: {{examples}}
+- Key (context only; do not output. Only use for context and if you are unsure about the \
+translation): {{key}}
+";
+
+// Closing instruction on the shape of the reply, for the format the request is sent in
+const TEXT_OUTPUT: &str = "- Output MUST be only the resulting text: no quotes, no code fences, \
+                           no labels, no explanations.\n";
+const JSON_OUTPUT: &str = "- Reply with a JSON object {\"translation\": \"...\"} holding only the \
+                           resulting text, no labels or explanations; keep line breaks as \\n.\n";
+
+// The system prompt as sent: the rendered template (built-in or custom) followed by the output
+// instruction for a JSON or plain text reply
+pub fn with_output_rule(system: &str, json: bool) -> String {
+    format!("{}{}", system, if json { JSON_OUTPUT } else { TEXT_OUTPUT })
+}
+
+// What one request's system prompt is built from
+#[derive(Clone, Copy)]
+pub struct PromptVars<'a> {
+    pub key: Option<&'a str>,
+    /// Source locale, or the fallback parent for adaptations
+    pub source_locale: &'a str,
+    pub target_locale: &'a str,
+    /// Source text, to pick the glossary entries that apply
+    pub text: &'a str,
+    /// Placeholders and protected terms that must survive verbatim
+    pub required: &'a [String],
+    pub settings: &'a LocaleSettings,
+    /// Similar (source, translation) pairs from the target locale
+    pub examples: &'a [(String, String)],
+}
+
+// System prompt templates and style guide of one project (`[prompt]`)
+#[derive(Debug, Clone)]
+pub struct Prompts {
+    translate: String,
+    adapt: String,
+    style_guide: String,
+}
+
+impl Default for Prompts {
+    fn default() -> Self {
+        Self {
+            translate: TRANSLATE_TEMPLATE.to_string(),
+            adapt: ADAPT_TEMPLATE.to_string(),
+            style_guide: String::new(),
+        }
+    }
+}
+
+impl Prompts {
+    pub fn from_config(cfg: &Config) -> Result<Self> {
+        let read = |path: &str| {
+            fs::read_to_string(path).with_context(|| format!("Reading prompt file {:?}", path))
+        };
+        let mut prompts = Self::default();
+        if let Some(path) = &cfg.prompt.template_file {
+            prompts.translate = check_variables(read(path)?, path)?;
+        }
+        if let Some(path) = &cfg.prompt.adapt_template_file {
+            prompts.adapt = check_variables(read(path)?, path)?;
+        }
+        let mut guide: Vec<String> = cfg.prompt.style_guide.iter().cloned().collect();
+        if let Some(path) = &cfg.prompt.style_guide_file {
+            guide.push(read(path)?);
+        }
+        prompts.style_guide = guide
+            .iter()
+            .map(|g| g.trim())
+            .filter(|g| !g.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(prompts)
+    }
+
+    // System prompt and user message for one key: the source text to translate, or the
+    // fallback parent's text (locale, text) to adapt
+    pub fn request(&self, vars: PromptVars, parent: Option<&(String, String)>) -> (String, String) {
+        match parent {
+            Some((locale, text)) => (
+                self.adaptation(&PromptVars {
+                    source_locale: locale,
+                    ..vars
+                }),
+                text.clone(),
+            ),
+            None => (self.translation(&vars), vars.text.to_string()),
+        }
+    }
+
+    pub fn translation(&self, vars: &PromptVars) -> String {
+        render(&self.translate, &self.values(vars))
+    }
+
+    pub fn adaptation(&self, vars: &PromptVars) -> String {
+        render(&self.adapt, &self.values(vars))
+    }
+
+    fn values(&self, vars: &PromptVars) -> Vec<(&'static str, String)> {
+        let text = vars.text.to_lowercase();
+        let glossary: Vec<String> = vars
+            .settings
+            .glossary
+            .iter()
+            .filter(|(term, _)| text.contains(&term.to_lowercase()))
+            .map(|(term, translation)| format!("{:?} -> {:?}", term, translation))
+            .collect();
+        let examples: Vec<String> = vars
+            .examples
+            .iter()
+            .map(|(source, target)| format!("{:?} -> {:?}", source, target))
+            .collect();
+        let formality = match vars.settings.formality {
+            Some(Formality::Formal) => {
+                "Use a formal register and the polite form of address \
+                 (e.g. German \"Sie\", French \"vous\")."
+            }
+            Some(Formality::Informal) => {
+                "Use an informal register and the familiar form of address \
+                 (e.g. German \"du\", Dutch \"je\")."
+            }
+            None => "",
+        };
+        vec![
+            ("source_locale", vars.source_locale.to_string()),
+            ("target_locale", vars.target_locale.to_string()),
+            ("key", vars.key.unwrap_or_default().to_string()),
+            ("placeholders", vars.required.join(", ")),
+            ("glossary", glossary.join(", ")),
+            ("formality", formality.to_string()),
+            (
+                "notes",
+                vars.settings
+                    .notes
+                    .as_deref()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+            ),
+            ("style_guide", self.style_guide.clone()),
+            ("examples", examples.join(", ")),
+        ]
+    }
+}
+
+fn variable_regex() -> Regex {
+    Regex::new(r"\{\{\s*([A-Za-z_]+)\s*\}\}").unwrap()
+}
+
+fn check_variables(template: String, path: &str) -> Result<String> {
+    for caps in variable_regex().captures_iter(&template) {
+        if !VARIABLES.contains(&&caps[1]) {
+            return Err(anyhow!(
+                "Unknown variable {{{{{}}}}} in {:?} (available: {})",
+                &caps[1],
+                path,
+                VARIABLES.join(", ")
+            ));
+        }
+    }
+    Ok(template)
+}
+
+fn render(template: &str, values: &[(&str, String)]) -> String {
+    let re = variable_regex();
+    let value = |name: &str| {
+        values
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v.as_str())
+            .unwrap_or_default()
+    };
+    let mut out = String::new();
+    for line in template.lines() {
+        if re
+            .captures_iter(line)
+            .any(|caps| value(&caps[1]).is_empty())
+        {
+            continue;
+        }
+        out.push_str(&re.replace_all(line, |caps: &Captures| value(&caps[1]).to_string()));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_reflects_locale_settings() {
+        let settings = LocaleSettings {
+            formality: Some(Formality::Formal),
+            notes: Some("Use ß, never ss.".into()),
+            glossary: [
+                ("cart".to_string(), "Warenkorb".to_string()),
+                ("invoice".to_string(), "Rechnung".to_string()),
+            ]
+            .into(),
+            ..LocaleSettings::default()
+        };
+        let vars = PromptVars {
+            key: None,
+            source_locale: "en-GB",
+            target_locale: "de-DE",
+            text: "Your Cart",
+            required: &[],
+            settings: &settings,
+            examples: &[("Your order".to_string(), "Ihre Bestellung".to_string())],
+        };
+        let prompt = Prompts::default().translation(&vars);
+        assert!(prompt.contains("\"Sie\""));
+        assert!(prompt.contains("- Style notes for de-DE: Use ß, never ss.\n"));
+        assert!(prompt.contains("- Use these translations for terms: \"cart\" -> \"Warenkorb\"\n"));
+        assert!(prompt.contains("consistent with: \"Your order\" -> \"Ihre Bestellung\"\n"));
+        assert!(!prompt.contains("Key (context") && !prompt.contains("Required placeholders"));
+        assert!(
+            !prompt.contains("Output MUST"),
+            "the output rule depends on the response format"
+        );
+        assert!(with_output_rule(&prompt, true).ends_with("keep line breaks as \\n.\n"));
+        assert!(with_output_rule(&prompt, false).ends_with("no explanations.\n"));
+    }
+
+    #[test]
+    fn custom_templates_are_checked_and_rendered() {
+        assert!(
+            check_variables("{{target}}".into(), "t.txt")
+                .unwrap_err()
+                .to_string()
+                .contains("{{target}}")
+        );
+        let values = [
+            ("target_locale", "fr-FR".to_string()),
+            ("key", String::new()),
+        ];
+        assert_eq!(
+            render(
+                "To {{ target_locale }}.\nKey: {{key}}\n{like_this}\n",
+                &values
+            ),
+            "To fr-FR.\n{like_this}\n"
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+// Concurrency gate that backs off when the API throttles us: halve the number of
+// in-flight requests on a 429, then grow back by one after a full window of successes.
+// A burst of 429s from requests that were already in flight counts as one cut, however long
+// those requests take to come back.
+pub struct AdaptiveLimiter {
+    sem: Semaphore,
+    max: usize,
+    state: Mutex<LimiterState>,
+}
+
+struct LimiterState {
+    limit: usize,
+    /// Permits to retire as they are released, to shrink the gate without waiting
+    debt: usize,
+    successes: usize,
+    /// Requests sent before this were in flight when the limit was last cut
+    last_cut: Option<Instant>,
+}
+
+impl AdaptiveLimiter {
+    pub fn new(max: usize) -> Self {
+        let max = max.max(1);
+        let state = LimiterState {
+            limit: max,
+            debt: 0,
+            successes: 0,
+            last_cut: None,
+        };
+        Self {
+            sem: Semaphore::new(max),
+            max,
+            state: Mutex::new(state),
+        }
+    }
+
+    pub async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        loop {
+            let permit = self.sem.acquire().await.ok()?;
+            let mut st = self.state.lock().unwrap();
+            if st.debt > 0 {
+                st.debt -= 1;
+                permit.forget();
+                continue;
+            }
+            return Some(permit);
+        }
+    }
+
+    // Whether the limit was cut for a throttled request sent at `sent`. Requests sent before the
+    // last cut belong to the burst that caused it and don't cut again.
+    pub fn on_throttle(&self, sent: Instant) -> bool {
+        let mut st = self.state.lock().unwrap();
+        if st.last_cut.is_some_and(|at| sent < at) {
+            return false;
+        }
+        let now = Instant::now();
+        let new_limit = (st.limit / 2).max(1);
+        st.debt += st.limit - new_limit;
+        st.limit = new_limit;
+        st.successes = 0;
+        st.last_cut = Some(now);
+        true
+    }
+
+    pub fn on_success(&self) {
+        let mut st = self.state.lock().unwrap();
+        if st.limit >= self.max {
+            return;
+        }
+        st.successes += 1;
+        if st.successes >= st.limit {
+            st.successes = 0;
+            st.limit += 1;
+            if st.debt > 0 {
+                st.debt -= 1;
+            } else {
+                self.sem.add_permits(1);
+            }
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit
+    }
+}
+
+// Token bucket for per-minute quotas (requests or tokens). Holds at most one second's
+// worth so a large quota is spread out, matching how providers enforce per-minute limits.
+pub struct TokenBucket {
+    capacity: f64,
+    per_sec: f64,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    pub fn per_minute(limit: u64) -> Self {
+        let per_sec = limit.max(1) as f64 / 60.0;
+        let capacity = per_sec.max(1.0);
+        Self {
+            capacity,
+            per_sec,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                last: Instant::now(),
+            }),
+        }
+    }
+
+    // Wait until `amount` can be taken. Amounts larger than the bucket go through once it is
+    // full and leave it in debt, so oversized requests are delayed rather than stuck forever.
+    pub async fn take(&self, amount: f64) {
+        loop {
+            let wait = {
+                let mut st = self.state.lock().unwrap();
+                let now = Instant::now();
+                st.tokens = (st.tokens + now.duration_since(st.last).as_secs_f64() * self.per_sec)
+                    .min(self.capacity);
+                st.last = now;
+                let needed = amount.min(self.capacity);
+                if st.tokens >= needed {
+                    st.tokens -= amount;
+                    return;
+                }
+                Duration::from_secs_f64((needed - st.tokens) / self.per_sec)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn halves_once_per_burst_and_recovers() {
+        let limiter = AdaptiveLimiter::new(8);
+        let sent = Instant::now();
+        tokio::time::advance(Duration::from_millis(100)).await;
+        assert!(limiter.on_throttle(sent));
+        // the rest of the burst does not cut again
+        for _ in 0..10 {
+            assert!(!limiter.on_throttle(sent));
+        }
+        assert_eq!(limiter.limit(), 4);
+        // retired permits are swallowed by acquire, leaving exactly `limit` usable
+        let mut held = Vec::new();
+        for _ in 0..4 {
+            held.push(limiter.acquire().await.unwrap());
+        }
+        assert_eq!(limiter.sem.available_permits(), 0);
+        drop(held);
+        for _ in 0..4 {
+            limiter.on_success();
+        }
+        assert_eq!(limiter.limit(), 5);
+        // sent after the cut: a new burst
+        assert!(limiter.on_throttle(Instant::now()));
+        assert_eq!(limiter.limit(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_requests_from_one_burst_cut_once() {
+        let limiter = AdaptiveLimiter::new(8);
+        let sent = Instant::now();
+        tokio::time::advance(Duration::from_millis(300)).await;
+        assert!(limiter.on_throttle(sent));
+        // a slower request of the same burst is throttled 600ms later, well past any retry delay
+        tokio::time::advance(Duration::from_millis(600)).await;
+        assert!(!limiter.on_throttle(sent));
+        assert_eq!(limiter.limit(), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn bucket_spreads_requests_over_time() {
+        // 600/min = 10/s with a burst of 10; paused time only moves while every task sleeps
+        let bucket = TokenBucket::per_minute(600);
+        let start = Instant::now();
+        for _ in 0..10 {
+            bucket.take(1.0).await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        bucket.take(1.0).await;
+        let waited = start.elapsed();
+        assert!(
+            waited >= Duration::from_millis(99) && waited <= Duration::from_millis(101),
+            "{:?}",
+            waited
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use crate::openai_client::TokenUsageSnapshot;
+use crate::translate::LocaleOutcome;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+// Machine-readable summary of one `translate` run, written with `--report <file>`
+#[derive(Debug, Serialize)]
+pub struct RunReport {
+    /// Unix timestamp (seconds) when the run started
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub model: String,
+    pub source_locale: String,
+    pub dry_run: bool,
+    pub interrupted: bool,
+    pub aborted: bool,
+    /// Across all projects; each locale entry has its own project's usage
+    pub usage: TokenUsageSnapshot,
+    pub locales: Vec<LocaleReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LocaleReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    pub locale: String,
+    pub translated: usize,
+    /// Do-not-translate keys copied from the source unchanged
+    pub copied: usize,
+    /// Keys taken from the fallback parent instead of being translated
+    pub inherited: usize,
+    /// Reused from the checkpoint of an interrupted run instead of requested again
+    pub resumed: usize,
+    /// Answered from a translation cache; always 0 until rustylang has one
+    pub cache_hits: usize,
+    pub skipped: Vec<String>,
+    pub failed: Vec<FailedKey>,
+    pub retries: u64,
+    pub placeholder_issues: Vec<PlaceholderIssue>,
+    pub latency_ms: Latency,
+    pub usage: TokenUsageSnapshot,
+    /// Why the locale could not be processed at all, e.g. an unreadable target file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailedKey {
+    pub key: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaceholderIssue {
+    pub key: String,
+    /// Placeholders from the source text that are absent from the translation
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Latency {
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+impl Latency {
+    pub fn from_samples(samples: &[Duration]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let mut ms: Vec<u64> = samples.iter().map(|d| d.as_millis() as u64).collect();
+        ms.sort_unstable();
+        // nearest-rank percentile
+        let pct = |p: usize| ms[(ms.len() * p).div_ceil(100).max(1) - 1];
+        Self {
+            p50: pct(50),
+            p90: pct(90),
+            p99: pct(99),
+            max: ms[ms.len() - 1],
+        }
+    }
+}
+
+impl LocaleReport {
+    pub fn new(outcome: &LocaleOutcome, usage: TokenUsageSnapshot) -> Self {
+        Self {
+            project: outcome.project.clone(),
+            locale: outcome.locale.clone(),
+            translated: outcome.translated,
+            copied: outcome.copied,
+            inherited: outcome.inherited,
+            resumed: outcome.resumed,
+            cache_hits: 0,
+            skipped: outcome.skipped.clone(),
+            failed: outcome
+                .failed
+                .iter()
+                .map(|(key, error)| FailedKey {
+                    key: key.clone(),
+                    error: error.clone(),
+                })
+                .collect(),
+            retries: usage.retries,
+            placeholder_issues: outcome.placeholder_issues.clone(),
+            latency_ms: Latency::from_samples(&outcome.latencies),
+            usage,
+            error: None,
+        }
+    }
+}
+
+impl RunReport {
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json).with_context(|| format!("Writing report {:?}", path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_percentiles_use_nearest_rank() {
+        let samples: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        let l = Latency::from_samples(&samples);
+        assert_eq!((l.p50, l.p90, l.p99, l.max), (50, 90, 99, 100));
+        let l = Latency::from_samples(&[Duration::from_millis(7)]);
+        assert_eq!((l.p50, l.p99, l.max), (7, 7, 7));
+    }
+}
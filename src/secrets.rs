@@ -0,0 +1,149 @@
+use crate::config::Config;
+use crate::git;
+use anyhow::{Context, Result, anyhow};
+use regex::Regex;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Command;
+use std::sync::{LazyLock, Mutex};
+use tracing::warn;
+
+// Keys in use this run; masked wherever `redact` is applied
+static SECRETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// OpenAI-style keys (`sk-...`, `sk-proj-...`) that were never registered, e.g. in a reply body
+static KEY_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"sk-[A-Za-z0-9_-]{16,}").unwrap());
+
+// The API key: OPENAI_API_KEY, then `api_key_command`, `api_key_file` or the plaintext `api_key`.
+// Warns when a plaintext key sits in a config file that git tracks, whichever source wins.
+pub fn api_key(cfg: &Config) -> Result<String> {
+    let ai = &cfg.openai;
+    if ai.api_key.is_some()
+        && let Some(origin) = cfg.provenance.get("openai.api_key")
+        && !origin.starts_with("env ")
+        && origin != "--set"
+        && git::is_tracked(Path::new(origin))
+    {
+        warn!(
+            "{} is tracked by git and contains a plaintext openai.api_key; \
+             use api_key_command, api_key_file or OPENAI_API_KEY and rotate the key",
+            origin
+        );
+    }
+    let key = match (
+        env::var("OPENAI_API_KEY")
+            .ok()
+            .filter(|k| !k.trim().is_empty()),
+        &ai.api_key_command,
+        &ai.api_key_file,
+    ) {
+        (Some(key), _, _) => key,
+        (None, Some(command), _) => key_from_command(command, cfg.root())?,
+        (None, None, Some(path)) => fs::read_to_string(path)
+            .with_context(|| format!("Reading openai.api_key_file {:?}", path))?,
+        (None, None, None) => ai.api_key.clone().unwrap_or_default(),
+    };
+    let key = key.trim().to_string();
+    if key.is_empty() {
+        return Err(anyhow!(
+            "No API key: set OPENAI_API_KEY, openai.api_key_command or openai.api_key_file"
+        ));
+    }
+    register(&key);
+    Ok(key)
+}
+
+// First line of the command's output; stderr is only shown (redacted) when it fails
+fn key_from_command(command: &str, dir: &Path) -> Result<String> {
+    let mut shell = if cfg!(windows) {
+        Command::new("cmd")
+    } else {
+        Command::new("sh")
+    };
+    shell
+        .arg(if cfg!(windows) { "/C" } else { "-c" })
+        .arg(command);
+    if !dir.as_os_str().is_empty() {
+        shell.current_dir(dir);
+    }
+    let out = shell
+        .output()
+        .with_context(|| format!("Running openai.api_key_command {:?}", command))?;
+    if !out.status.success() {
+        let stderr = redact(String::from_utf8_lossy(&out.stderr).trim());
+        return Err(anyhow!(
+            "openai.api_key_command {:?} failed ({}): {}",
+            command,
+            out.status,
+            stderr
+        ));
+    }
+    let stdout =
+        String::from_utf8(out.stdout).context("openai.api_key_command printed invalid UTF-8")?;
+    Ok(stdout.lines().next().unwrap_or_default().to_string())
+}
+
+pub fn register(secret: &str) {
+    let mut secrets = SECRETS.lock().unwrap();
+    if !secret.is_empty() && !secrets.iter().any(|s| s == secret) {
+        secrets.push(secret.to_string());
+    }
+}
+
+// `text` with registered keys and anything shaped like an OpenAI key masked
+pub fn redact(text: &str) -> String {
+    let mut out = text.to_string();
+    for secret in SECRETS.lock().unwrap().iter() {
+        out = out.replace(secret.as_str(), "<redacted>");
+    }
+    KEY_PATTERN.replace_all(&out, "sk-<redacted>").into_owned()
+}
+
+// Log writer that redacts each formatted event before it reaches stdout
+pub struct RedactingWriter;
+
+impl Write for RedactingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stdout().write_all(redact(&String::from_utf8_lossy(buf)).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_and_openai_shaped_keys_are_masked() {
+        register("azure-0123456789");
+        let text =
+            redact("auth failed for azure-0123456789 and sk-proj-AbCdEf0123456789xyz (sk-short)");
+        assert_eq!(
+            text,
+            "auth failed for <redacted> and sk-<redacted> (sk-short)"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_output_is_the_key_and_failures_are_redacted() {
+        assert_eq!(
+            key_from_command("printf 'sk-local\\nsecond line'", Path::new("")).unwrap(),
+            "sk-local"
+        );
+        let err = key_from_command(
+            "printf 'sk-%s' leaked0123456789abcdef >&2; exit 3",
+            Path::new(""),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.ends_with("): sk-<redacted>"), "{}", err);
+    }
+}
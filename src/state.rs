@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// Bookkeeping that doesn't belong in the locale files themselves, keyed by locale then dot path
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TranslationState {
+    #[serde(default)]
+    pub locales: BTreeMap<String, BTreeMap<String, KeyState>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyState {
+    /// Hash of the source text the translation was made from
+    pub source_hash: String,
+    /// The value is the source text written as a stand-in after a failed translation
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub untranslated: bool,
+    /// Hash of the fallback parent's text when a `diff_only` adaptation came back unchanged,
+    /// so nothing was written and the key is left to the parent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_hash: Option<String>,
+}
+
+impl TranslationState {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let s = fs::read_to_string(path).with_context(|| format!("Reading {:?}", path))?;
+        serde_json::from_str(&s).with_context(|| format!("Parsing state file {:?}", path))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp_path, path).with_context(|| format!("Writing {:?}", path))?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, locale: &str, path: &str, source_text: &str) {
+        self.insert(
+            locale,
+            path,
+            KeyState {
+                source_hash: source_hash(source_text),
+                untranslated: false,
+                parent_hash: None,
+            },
+        );
+    }
+
+    pub fn record_untranslated(&mut self, locale: &str, path: &str, source_text: &str) {
+        self.insert(
+            locale,
+            path,
+            KeyState {
+                source_hash: source_hash(source_text),
+                untranslated: true,
+                parent_hash: None,
+            },
+        );
+    }
+
+    pub fn record_inherited(
+        &mut self,
+        locale: &str,
+        path: &str,
+        source_text: &str,
+        parent_text: &str,
+    ) {
+        let key = KeyState {
+            source_hash: source_hash(source_text),
+            untranslated: false,
+            parent_hash: Some(source_hash(parent_text)),
+        };
+        self.insert(locale, path, key);
+    }
+
+    fn insert(&mut self, locale: &str, path: &str, key: KeyState) {
+        self.locales
+            .entry(locale.to_string())
+            .or_default()
+            .insert(path.to_string(), key);
+    }
+
+    pub fn is_untranslated(&self, locale: &str, path: &str) -> bool {
+        self.locales
+            .get(locale)
+            .and_then(|keys| keys.get(path))
+            .is_some_and(|k| k.untranslated)
+    }
+
+    // The key was adapted to exactly the parent's text before, and neither text has changed since
+    pub fn inherits_unchanged(
+        &self,
+        locale: &str,
+        path: &str,
+        source_text: &str,
+        parent_text: &str,
+    ) -> bool {
+        self.locales
+            .get(locale)
+            .and_then(|keys| keys.get(path))
+            .is_some_and(|k| {
+                k.source_hash == source_hash(source_text)
+                    && k.parent_hash.as_deref() == Some(source_hash(parent_text).as_str())
+            })
+    }
+
+    // A translation is stale when its source text changed since it was written.
+    // Keys with no recorded state are never reported as stale.
+    pub fn is_stale(&self, locale: &str, path: &str, source_text: &str) -> bool {
+        self.locales
+            .get(locale)
+            .and_then(|keys| keys.get(path))
+            .is_some_and(|k| k.source_hash != source_hash(source_text))
+    }
+}
+
+// FNV-1a: stable across Rust releases, unlike DefaultHasher
+pub fn source_hash(text: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in text.bytes() {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_only_when_recorded_source_changed() {
+        let mut state = TranslationState::default();
+        state.record("fr-FR", "a.b", "Hello");
+        assert!(!state.is_stale("fr-FR", "a.b", "Hello"));
+        assert!(state.is_stale("fr-FR", "a.b", "Hello there"));
+        assert!(!state.is_stale("fr-FR", "other", "anything"));
+    }
+}
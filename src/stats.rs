@@ -0,0 +1,168 @@
+use crate::state::TranslationState;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LocaleStats {
+    pub locale: String,
+    pub total: usize,
+    /// Present and non-empty (includes identical-to-source)
+    pub translated: usize,
+    pub missing: usize,
+    pub empty: usize,
+    pub identical: usize,
+    /// Source text written as a stand-in after a failed translation (not counted as translated)
+    pub fallback: usize,
+    pub stale: usize,
+    /// Words/characters of source text still waiting for a translation
+    pub untranslated_words: usize,
+    pub untranslated_chars: usize,
+}
+
+impl LocaleStats {
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            return 100.0;
+        }
+        self.translated as f64 * 100.0 / self.total as f64
+    }
+}
+
+pub fn compute_stats(
+    locale: &str,
+    source: &BTreeMap<String, String>,
+    target: &BTreeMap<String, String>,
+    state: &TranslationState,
+) -> LocaleStats {
+    let mut st = LocaleStats {
+        locale: locale.to_string(),
+        total: source.len(),
+        ..Default::default()
+    };
+    for (path, english) in source {
+        match target.get(path) {
+            Some(t) if !t.is_empty() && state.is_untranslated(locale, path) => st.fallback += 1,
+            Some(t) if !t.is_empty() => {
+                st.translated += 1;
+                if t == english {
+                    st.identical += 1;
+                }
+                if state.is_stale(locale, path, english) {
+                    st.stale += 1;
+                }
+                continue;
+            }
+            Some(_) => st.empty += 1,
+            None => st.missing += 1,
+        }
+        st.untranslated_words += english.split_whitespace().count();
+        st.untranslated_chars += english.chars().count();
+    }
+    st
+}
+
+const HEADERS: [&str; 11] = [
+    "locale",
+    "total",
+    "translated",
+    "%",
+    "missing",
+    "empty",
+    "identical",
+    "fallback",
+    "stale",
+    "words",
+    "chars",
+];
+
+fn row(s: &LocaleStats) -> [String; 11] {
+    [
+        s.locale.clone(),
+        s.total.to_string(),
+        s.translated.to_string(),
+        format!("{:.1}", s.percent()),
+        s.missing.to_string(),
+        s.empty.to_string(),
+        s.identical.to_string(),
+        s.fallback.to_string(),
+        s.stale.to_string(),
+        s.untranslated_words.to_string(),
+        s.untranslated_chars.to_string(),
+    ]
+}
+
+pub fn render_table(stats: &[LocaleStats]) -> String {
+    let rows: Vec<[String; 11]> = stats.iter().map(row).collect();
+    let mut widths = HEADERS.map(str::len);
+    for r in &rows {
+        for (w, cell) in widths.iter_mut().zip(r) {
+            *w = (*w).max(cell.len());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(widths)
+            .enumerate()
+            // left-align the locale column, right-align the numbers
+            .map(|(i, (c, w))| {
+                if i == 0 {
+                    format!("{:<w$}", c)
+                } else {
+                    format!("{:>w$}", c)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+    let mut out = line(HEADERS.to_vec());
+    out.push('\n');
+    for r in &rows {
+        out.push_str(&line(r.iter().map(String::as_str).collect()));
+        out.push('\n');
+    }
+    out
+}
+
+pub fn render_markdown(stats: &[LocaleStats]) -> String {
+    let mut out = format!("| {} |\n", HEADERS.join(" | "));
+    out.push_str(&format!("|{}\n", "---|".repeat(HEADERS.len())));
+    for s in stats {
+        out.push_str(&format!("| {} |\n", row(s).join(" | ")));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_each_category() {
+        let source: BTreeMap<String, String> = [
+            ("a", "Hello world"),
+            ("b", "OK"),
+            ("c", "Bye"),
+            ("d", "New"),
+            ("e", "Later"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let target: BTreeMap<String, String> =
+            [("a", "Bonjour"), ("b", "OK"), ("c", ""), ("e", "Later")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+        let mut state = TranslationState::default();
+        state.record("fr-FR", "a", "Hello");
+        state.record_untranslated("fr-FR", "e", "Later");
+        let st = compute_stats("fr-FR", &source, &target, &state);
+        assert_eq!(
+            (st.total, st.translated, st.missing, st.empty),
+            (5, 2, 1, 1)
+        );
+        assert_eq!((st.identical, st.fallback, st.stale), (1, 1, 1));
+        assert_eq!((st.untranslated_words, st.untranslated_chars), (3, 11));
+    }
+}
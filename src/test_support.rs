@@ -0,0 +1,96 @@
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::{fs, process, thread};
+
+// Fresh, empty directory for one test
+pub fn temp_dir(name: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!("rustylang-{}-{}-{}", name, process::id(), n));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Local stand-in for the chat completions endpoint. Every request body is recorded and
+// answered with `reply(body)` as (status, JSON body); connections are closed after each reply.
+pub struct MockApi {
+    pub endpoint: String,
+    pub requests: Arc<Mutex<Vec<Value>>>,
+}
+
+impl MockApi {
+    pub fn start(reply: impl Fn(&Value) -> (u16, String) + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!(
+            "http://{}/v1/chat/completions",
+            listener.local_addr().unwrap()
+        );
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let (reply, seen) = (Arc::new(reply), requests.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let (reply, seen) = (reply.clone(), seen.clone());
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut length = 0;
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                            return;
+                        }
+                        if line.trim().is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':')
+                            && name.eq_ignore_ascii_case("content-length")
+                        {
+                            length = value.trim().parse().unwrap_or(0);
+                        }
+                    }
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+                    let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+                    let (status, text) = reply(&body);
+                    seen.lock().unwrap().push(body);
+                    let head = format!(
+                        "HTTP/1.1 {} X\r\ncontent-type: application/json\r\n\
+                         content-length: {}\r\nconnection: close\r\n\r\n",
+                        status,
+                        text.len()
+                    );
+                    let _ = stream
+                        .write_all(head.as_bytes())
+                        .and_then(|_| stream.write_all(text.as_bytes()));
+                });
+            }
+        });
+        Self { endpoint, requests }
+    }
+
+    pub fn request_count(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+}
+
+// A chat completion whose message content is `content`
+pub fn chat_reply(content: &str, finish_reason: &str) -> (u16, String) {
+    let body = serde_json::json!({
+        "choices": [{ "message": { "content": content }, "finish_reason": finish_reason }],
+        "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+    });
+    (200, body.to_string())
+}
+
+// JSON-mode reply carrying `translation`
+pub fn translation_reply(translation: &str) -> (u16, String) {
+    chat_reply(
+        &serde_json::json!({ "translation": translation }).to_string(),
+        "stop",
+    )
+}
@@ -0,0 +1,845 @@
+use crate::checkpoint::Checkpoint;
+use crate::config::{Config, FailurePolicy, FallbackMode, Format, LocaleSettings, Locales};
+use crate::diff::{keys_to_translate, prune_orphans};
+use crate::errors::RustyLangError;
+use crate::examples::ExamplePool;
+use crate::json_utils::{apply_key_order, write_json_atomic};
+use crate::locales::{LocaleFile, flatten_locale, read_locale, set_locale_value};
+use crate::openai_client::OpenAiTranslator;
+use crate::patterns::{KeyFilter, KeyMatcher};
+use crate::prompt::{PromptVars, Prompts};
+use crate::report::PlaceholderIssue;
+use crate::state::TranslationState;
+use anyhow::Result;
+use futures::{StreamExt, stream};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+// Everything shared by the per-locale tasks of one `translate` run
+pub struct TranslateRun {
+    /// Shared by every project of the run
+    pub translator: Arc<OpenAiTranslator>,
+    project: Option<String>,
+    /// Only these keys are translated (forced, even if the target has a value); from `--since`
+    pub changed_keys: Option<BTreeSet<String>>,
+    /// Source locale files, one per namespace
+    sources: Vec<LocaleFile>,
+    /// Source strings keyed by namespace-qualified dot path
+    source_flat: BTreeMap<String, String>,
+    source_locale: String,
+    file_pattern: String,
+    format: Format,
+    concurrency: usize,
+    /// `[locales."xx"]` overrides and fallback chains; locales without an entry use the defaults
+    locales: Locales,
+    overwrite: bool,
+    dry_run: bool,
+    /// Keys this run may touch (`include`/`exclude`, `--only`/`--exclude`)
+    filter: KeyFilter,
+    /// Keys copied from the source unchanged instead of being translated
+    verbatim: KeyMatcher,
+    protected_terms: Vec<String>,
+    prompts: Prompts,
+    /// Few-shot examples per request, unless the locale sets its own
+    examples: usize,
+    /// Some when orphaned keys should be pruned, holding the keep patterns
+    prune_keep: Option<KeyMatcher>,
+    pub state: Mutex<TranslationState>,
+    pub checkpoint: Mutex<Checkpoint>,
+    pub checkpoint_file: PathBuf,
+    /// Save the checkpoint after this many completed keys (0 disables periodic saves)
+    checkpoint_every: usize,
+    on_failure: FailurePolicy,
+    /// Set on Ctrl-C: queued keys are skipped, finished ones are still written
+    pub interrupted: Arc<AtomicBool>,
+    /// Set by the first failure under `FailurePolicy::Abort`; behaves like an interrupt
+    pub aborted: Arc<AtomicBool>,
+    progress: MultiProgress,
+    progress_style: ProgressStyle,
+    completed_since_save: AtomicUsize,
+}
+
+#[derive(Debug, Default)]
+pub struct LocaleOutcome {
+    pub project: Option<String>,
+    pub locale: String,
+    /// Keys left untranslated because the budget ran out or the run was interrupted/aborted
+    pub skipped: Vec<String>,
+    /// Keys whose translation request failed, with the error
+    pub failed: Vec<(String, String)>,
+    pub translated: usize,
+    /// Do-not-translate keys whose source text was copied unchanged
+    pub copied: usize,
+    /// Keys taken from the fallback parent: copied, left to the parent (`diff_only`), or adapted
+    /// unchanged
+    pub inherited: usize,
+    /// Keys restored from the checkpoint instead of being requested again
+    pub resumed: usize,
+    pub placeholder_issues: Vec<PlaceholderIssue>,
+    /// Wall time of each translation request, including retries and rate-limit waits
+    pub latencies: Vec<Duration>,
+}
+
+impl LocaleOutcome {
+    // `project/locale` in multi-project configs; also keys the locale's token usage
+    pub fn label(&self) -> String {
+        label(self.project.as_deref(), &self.locale)
+    }
+}
+
+fn label(project: Option<&str>, locale: &str) -> String {
+    match project {
+        Some(p) => format!("{}/{}", p, locale),
+        None => locale.to_string(),
+    }
+}
+
+enum KeyResult {
+    Translated(String, Duration),
+    /// Not attempted: budget reached, or the run was interrupted or aborted
+    Skipped,
+    Failed(String),
+}
+
+impl TranslateRun {
+    pub fn new(
+        cfg: &Config,
+        translator: Arc<OpenAiTranslator>,
+        sources: Vec<LocaleFile>,
+        overwrite: bool,
+        dry_run: bool,
+    ) -> Result<Self> {
+        let prune_keep = if cfg.translate.prune_orphans {
+            Some(KeyMatcher::new(&cfg.translate.prune_keep)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            translator,
+            project: cfg.project.clone(),
+            changed_keys: None,
+            source_flat: flatten_locale(&sources),
+            sources,
+            source_locale: cfg.source_locale.clone(),
+            file_pattern: cfg.file_pattern.clone(),
+            format: cfg.format.clone(),
+            concurrency: cfg.concurrency,
+            locales: cfg.locales.clone(),
+            overwrite,
+            dry_run,
+            filter: KeyFilter::new(&cfg.translate.include, &cfg.translate.exclude)?,
+            verbatim: KeyMatcher::new(&cfg.translate.verbatim)?,
+            protected_terms: cfg.translate.protected_terms.clone(),
+            prompts: Prompts::from_config(cfg)?,
+            examples: cfg.translate.examples,
+            prune_keep,
+            state: Mutex::new(TranslationState::load(Path::new(&cfg.state_file))?),
+            checkpoint: Mutex::new(Checkpoint {
+                overwrite,
+                ..Checkpoint::default()
+            }),
+            checkpoint_file: PathBuf::from(&cfg.checkpoint_file),
+            checkpoint_every: cfg.translate.checkpoint_every,
+            on_failure: cfg.translate.on_failure,
+            interrupted: Arc::new(AtomicBool::new(false)),
+            aborted: Arc::new(AtomicBool::new(false)),
+            progress: MultiProgress::new(),
+            progress_style: ProgressStyle::with_template("{msg} {bar:40.cyan/blue} {pos}/{len}")
+                .unwrap()
+                .progress_chars("##-"),
+            completed_since_save: AtomicUsize::new(0),
+        })
+    }
+
+    pub async fn run_locale(&self, locale: String) -> Result<LocaleOutcome> {
+        let mut outcome = LocaleOutcome {
+            project: self.project.clone(),
+            locale: locale.clone(),
+            ..Default::default()
+        };
+        if locale == self.source_locale {
+            return Ok(outcome);
+        }
+        let resumed = {
+            let cp = self.checkpoint.lock().unwrap();
+            if cp.completed_locales.contains(&locale) {
+                info!(locale=%locale, "Already completed in checkpoint");
+                return Ok(outcome);
+            }
+            cp.keys.get(&locale).cloned().unwrap_or_default()
+        };
+
+        let namespaces: Vec<Option<String>> =
+            self.sources.iter().map(|f| f.namespace.clone()).collect();
+        let mut targets = read_locale(&self.file_pattern, &locale, &namespaces)?;
+        let originals: Vec<Value> = targets.iter().map(|t| t.json.clone()).collect();
+        // Translations finished by an interrupted run are applied without asking again
+        for (path, txt) in &resumed {
+            set_locale_value(&mut targets, path, Value::String(txt.clone()), true)?;
+        }
+        let target_flat = flatten_locale(&targets);
+        let mut to_fill = keys_to_translate(
+            &self.source_flat,
+            &target_flat,
+            self.overwrite,
+            self.changed_keys.as_ref(),
+        );
+        // Source text written as a stand-in after a failure is not a translation: retry it
+        if self.changed_keys.is_none() {
+            let state = self.state.lock().unwrap();
+            let queued: HashSet<String> = to_fill.iter().map(|(p, _)| p.clone()).collect();
+            for (path, english) in &self.source_flat {
+                if !queued.contains(path) && state.is_untranslated(&locale, path) {
+                    to_fill.push((path.clone(), english.clone()));
+                }
+            }
+        }
+        to_fill.retain(|(path, _)| self.filter.allows(path) && !resumed.contains_key(path));
+        // Do-not-translate keys always mirror the source, whatever the target currently holds
+        let copied: Vec<(String, String)> = self
+            .source_flat
+            .iter()
+            .filter(|(path, english)| {
+                self.verbatim.is_match(path)
+                    && self.filter.allows(path)
+                    && self
+                        .changed_keys
+                        .as_ref()
+                        .is_none_or(|keys| keys.contains(*path))
+                    && target_flat.get(*path) != Some(*english)
+            })
+            .map(|(path, english)| (path.clone(), english.clone()))
+            .collect();
+        to_fill.retain(|(path, _)| !self.verbatim.is_match(path));
+        if !copied.is_empty() {
+            info!(
+                locale = %locale, count = %copied.len(), dry_run = %self.dry_run,
+                "Copied do-not-translate keys verbatim"
+            );
+            for (path, english) in &copied {
+                set_locale_value(&mut targets, path, Value::String(english.clone()), true)?;
+            }
+        }
+        // Keys a fallback parent already has are copied from it or adapted, not translated again
+        let settings = self
+            .locales
+            .settings()
+            .get(&locale)
+            .cloned()
+            .unwrap_or_default();
+        let chain = self.locales.fallback_chain(&locale);
+        let mut parents = BTreeMap::new();
+        if !chain.is_empty() {
+            let mut values = fallback_values(&self.file_pattern, &namespaces, &chain)?;
+            let state = self.state.lock().unwrap();
+            values.retain(|path, (parent, _)| !state.is_untranslated(parent, path));
+            parents = to_fill
+                .iter()
+                .filter_map(|(path, _)| values.remove_entry(path))
+                .collect();
+        }
+        let mut inherited = Vec::new();
+        if settings.fallback_mode.unwrap_or(FallbackMode::Copy) == FallbackMode::Copy {
+            to_fill.retain(|(path, english)| {
+                let Some((_, text)) = parents.get(path) else {
+                    return true;
+                };
+                inherited.push((path.clone(), english.clone(), text.clone()));
+                false
+            });
+            if !inherited.is_empty() {
+                info!(
+                    locale = %locale, count = %inherited.len(), fallback = %chain.join(" -> "),
+                    "Inherited keys from fallback"
+                );
+            }
+            outcome.inherited = inherited.len();
+            // with diff_only the runtime falls back to the parent, so nothing is written
+            if settings.diff_only {
+                inherited.clear();
+            }
+            for (path, _, text) in &inherited {
+                set_locale_value(&mut targets, path, Value::String(text.clone()), true)?;
+            }
+        } else if settings.diff_only && !self.overwrite {
+            // not written last time because the adaptation matched the parent: don't ask again
+            let state = self.state.lock().unwrap();
+            let before = to_fill.len();
+            to_fill.retain(|(path, english)| {
+                !parents
+                    .get(path)
+                    .is_some_and(|(_, text)| state.inherits_unchanged(&locale, path, english, text))
+            });
+            outcome.inherited = before - to_fill.len();
+        }
+        let mut pruned = Vec::new();
+        if let Some(keep) = &self.prune_keep {
+            for (target, source) in targets.iter_mut().zip(&self.sources) {
+                pruned.extend(prune_orphans(
+                    &mut target.json,
+                    &source.json,
+                    source.namespace.as_deref(),
+                    keep,
+                ));
+            }
+        }
+        if !pruned.is_empty() {
+            info!(
+                locale = %locale, count = %pruned.len(), dry_run = %self.dry_run,
+                "Pruned orphaned keys"
+            );
+        }
+        if to_fill.is_empty()
+            && pruned.is_empty()
+            && resumed.is_empty()
+            && copied.is_empty()
+            && inherited.is_empty()
+        {
+            info!(locale=%locale, "No translations needed");
+            if !self.dry_run {
+                self.checkpoint.lock().unwrap().complete_locale(&locale);
+            }
+            return Ok(outcome);
+        }
+
+        let pb = self.progress.add(ProgressBar::new(to_fill.len() as u64));
+        pb.set_style(self.progress_style.clone());
+        pb.set_message(locale.clone());
+
+        let examples = settings.examples.unwrap_or(self.examples);
+        let pool = if examples > 0 {
+            let pending: HashSet<&str> = to_fill.iter().map(|(path, _)| path.as_str()).collect();
+            let state = self.state.lock().unwrap();
+            ExamplePool::new(&self.source_flat, &target_flat, |path| {
+                pending.contains(path) || state.is_untranslated(&locale, path)
+            })
+        } else {
+            ExamplePool::default()
+        };
+        let concurrency = settings
+            .concurrency
+            .map_or(self.concurrency, |c| c.min(self.concurrency));
+        let mut results = stream::iter(to_fill)
+            .map(|(path, english)| {
+                let parent = parents.get(&path);
+                let examples = pool.most_similar(&path, &english, examples);
+                self.translate_key(&locale, &settings, parent, examples, path, english)
+            })
+            .buffer_unordered(concurrency);
+        let mut written = Vec::new();
+        let mut fallbacks = Vec::new();
+        let mut unchanged = Vec::new();
+        while let Some((path, english, result)) = results.next().await {
+            pb.inc(1);
+            match result {
+                KeyResult::Skipped => outcome.skipped.push(path),
+                KeyResult::Translated(_, _) if self.dry_run => written.push((path, english)),
+                KeyResult::Translated(txt, elapsed)
+                    if settings.diff_only && parents.get(&path).is_some_and(|(_, t)| *t == txt) =>
+                {
+                    outcome.latencies.push(elapsed);
+                    outcome.inherited += 1;
+                    unchanged.push((path, english, txt));
+                }
+                KeyResult::Translated(txt, elapsed) => {
+                    outcome.latencies.push(elapsed);
+                    let missing: Vec<String> = self
+                        .required_tokens(&english)
+                        .into_iter()
+                        .filter(|p| !txt.contains(p.as_str()))
+                        .collect();
+                    if !missing.is_empty() {
+                        warn!(
+                            locale = %locale, path = %path, ?missing,
+                            "Translation is missing placeholders"
+                        );
+                        outcome.placeholder_issues.push(PlaceholderIssue {
+                            key: path.clone(),
+                            missing,
+                        });
+                    }
+                    self.checkpoint_key(&locale, &path, &txt)?;
+                    set_locale_value(&mut targets, &path, Value::String(txt), true)?;
+                    written.push((path, english));
+                }
+                KeyResult::Failed(err) => {
+                    error!(
+                        locale = %locale, path = %path, error = %err, policy = ?self.on_failure,
+                        "Translation failed"
+                    );
+                    match self.on_failure {
+                        FailurePolicy::Skip => {}
+                        FailurePolicy::Source => {
+                            if !self.dry_run {
+                                set_locale_value(
+                                    &mut targets,
+                                    &path,
+                                    Value::String(english.clone()),
+                                    true,
+                                )?;
+                            }
+                            fallbacks.push((path.clone(), english));
+                        }
+                        FailurePolicy::Abort => self.aborted.store(true, Ordering::Relaxed),
+                    }
+                    outcome.failed.push((path, err));
+                }
+            }
+        }
+        drop(results);
+
+        pb.finish_and_clear();
+        if self.dry_run {
+            info!(locale=%locale, count=%written.len(), "Dry run: would update keys");
+            return Ok(outcome);
+        }
+
+        for ((target, source), original) in targets.iter_mut().zip(&self.sources).zip(&originals) {
+            if target.json == *original {
+                continue;
+            }
+            apply_key_order(&mut target.json, self.format.sort_keys, Some(&source.json));
+            write_json_atomic(&target.path, &target.json, &self.format)?;
+            info!(locale=%locale, file=?target.path, "Wrote translations");
+        }
+
+        let mut state = self.state.lock().unwrap();
+        for (path, english) in written.iter().chain(&copied) {
+            state.record(&locale, path, english);
+        }
+        for (path, english, _) in &inherited {
+            state.record(&locale, path, english);
+        }
+        for (path, english, parent_text) in &unchanged {
+            state.record_inherited(&locale, path, english, parent_text);
+        }
+        for (path, english) in &fallbacks {
+            state.record_untranslated(&locale, path, english);
+        }
+        for path in resumed.keys() {
+            if let Some(english) = self.source_flat.get(path) {
+                state.record(&locale, path, english);
+            }
+        }
+        // Skipped keys keep the locale (and its finished keys) in the checkpoint for --resume
+        if outcome.skipped.is_empty() {
+            self.checkpoint.lock().unwrap().complete_locale(&locale);
+        }
+        outcome.translated = written.len();
+        outcome.copied = copied.len();
+        outcome.resumed = resumed.len();
+        outcome.skipped.sort();
+        outcome.failed.sort();
+        outcome.placeholder_issues.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(outcome)
+    }
+
+    async fn translate_key(
+        &self,
+        locale: &str,
+        settings: &LocaleSettings,
+        parent: Option<&(String, String)>,
+        examples: Vec<(String, String)>,
+        path: String,
+        english: String,
+    ) -> (String, String, KeyResult) {
+        if self.interrupted.load(Ordering::Relaxed) || self.aborted.load(Ordering::Relaxed) {
+            return (path, english, KeyResult::Skipped);
+        }
+        if self.dry_run {
+            return (
+                path,
+                english,
+                KeyResult::Translated(String::from("<translated>"), Duration::ZERO),
+            );
+        }
+        let placeholders = self.required_tokens(&english);
+        let started = Instant::now();
+        let vars = PromptVars {
+            key: Some(&path),
+            source_locale: &self.source_locale,
+            target_locale: locale,
+            text: &english,
+            required: &placeholders,
+            settings,
+            examples: &examples,
+        };
+        let (system, text) = self.prompts.request(vars, parent);
+        let usage_key = label(self.project.as_deref(), locale);
+        let response = self
+            .translator
+            .translate(system, &text, &usage_key, settings)
+            .await;
+        let result = match response {
+            Ok(tx) => KeyResult::Translated(tx, started.elapsed()),
+            Err(err)
+                if err
+                    .downcast_ref::<RustyLangError>()
+                    .is_some_and(|e| matches!(e, RustyLangError::BudgetExceeded(_))) =>
+            {
+                KeyResult::Skipped
+            }
+            Err(err) => KeyResult::Failed(format!("{:#}", err)),
+        };
+        (path, english, result)
+    }
+
+    fn required_tokens(&self, english: &str) -> Vec<String> {
+        required_tokens(english, &self.protected_terms)
+    }
+
+    fn checkpoint_key(&self, locale: &str, path: &str, txt: &str) -> Result<()> {
+        let mut cp = self.checkpoint.lock().unwrap();
+        cp.record(locale, path, txt);
+        if self.checkpoint_every > 0
+            && self.completed_since_save.fetch_add(1, Ordering::Relaxed) + 1
+                >= self.checkpoint_every
+        {
+            self.completed_since_save.store(0, Ordering::Relaxed);
+            cp.save(&self.checkpoint_file)?;
+        }
+        Ok(())
+    }
+}
+
+// The nearest non-empty parent translation of every key along a fallback chain:
+// path -> (locale, text)
+pub fn fallback_values(
+    file_pattern: &str,
+    namespaces: &[Option<String>],
+    chain: &[String],
+) -> Result<BTreeMap<String, (String, String)>> {
+    let mut out = BTreeMap::new();
+    for parent in chain {
+        for (path, text) in flatten_locale(&read_locale(file_pattern, parent, namespaces)?) {
+            if !text.trim().is_empty() {
+                out.entry(path).or_insert_with(|| (parent.clone(), text));
+            }
+        }
+    }
+    Ok(out)
+}
+
+// Locales grouped so each fallback parent in the run is translated before its variants
+pub fn fallback_waves(locales: &[String], config: &Locales) -> Vec<Vec<String>> {
+    let mut waves: Vec<Vec<String>> = Vec::new();
+    for locale in locales {
+        let depth = config
+            .fallback_chain(locale)
+            .iter()
+            .filter(|p| locales.contains(p))
+            .count();
+        if waves.len() <= depth {
+            waves.resize(depth + 1, Vec::new());
+        }
+        waves[depth].push(locale.clone());
+    }
+    waves.retain(|w| !w.is_empty());
+    waves
+}
+
+// Placeholders plus any protected terms present in the text: all must survive translation verbatim
+pub fn required_tokens(s: &str, protected_terms: &[String]) -> Vec<String> {
+    let mut out = extract_placeholders(s);
+    for term in protected_terms {
+        if !term.is_empty() && s.contains(term.as_str()) && !out.contains(term) {
+            out.push(term.clone());
+        }
+    }
+    out
+}
+
+pub fn extract_placeholders(s: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    // Patterns: {word}, {{mustache}}, :named, %s, %d, {0}, {name}
+    let patterns = [
+        Regex::new(r"\{\{[^}]+\}\}").unwrap(),
+        Regex::new(r"\{[^}]+\}").unwrap(),
+        Regex::new(r":[A-Za-z_][A-Za-z0-9_]*").unwrap(),
+        Regex::new(r"%[sd]?").unwrap(),
+    ];
+    for re in patterns.iter() {
+        for m in re.find_iter(s) {
+            let p = m.as_str().to_string();
+            if !out.contains(&p) {
+                out.push(p);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OpenAi;
+    use crate::test_support::{MockApi, temp_dir, translation_reply};
+    use std::fs;
+
+    // A run over `{locale}.json` files in `dir`, translating through `api`
+    fn test_run(dir: &Path, extra: &str, api: &MockApi) -> TranslateRun {
+        let toml = format!(
+            "source_locale = \"en-GB\"\nfile_pattern = {:?}\nstate_file = {:?}\n\
+             checkpoint_file = {:?}\n{}",
+            dir.join("{locale}.json"),
+            dir.join("state.json"),
+            dir.join("checkpoint.json"),
+            extra
+        );
+        let cfg: Config = toml::from_str(&toml).unwrap();
+        let ai = OpenAi {
+            max_retries: 0,
+            ..OpenAi::default()
+        };
+        let translator = OpenAiTranslator::new("sk-test".into(), &ai, 4)
+            .unwrap()
+            .with_endpoint(api.endpoint.clone());
+        let sources = crate::locales::read_locale(&cfg.file_pattern, "en-GB", &[None]).unwrap();
+        TranslateRun::new(&cfg, Arc::new(translator), sources, false, false).unwrap()
+    }
+
+    fn read_json(path: PathBuf) -> Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn interrupted_runs_keep_the_locale_open_and_resume_without_requests() {
+        let dir = temp_dir("resume");
+        fs::write(
+            dir.join("en-GB.json"),
+            r#"{"hello": "Hello", "bye": "Goodbye"}"#,
+        )
+        .unwrap();
+        let api = MockApi::start(|_| translation_reply("Bonjour"));
+        let run = test_run(&dir, "", &api);
+        run.interrupted.store(true, Ordering::Relaxed);
+        let outcome = run.run_locale("fr-FR".into()).await.unwrap();
+        assert_eq!(outcome.skipped, ["bye", "hello"]);
+        assert!(
+            !run.checkpoint
+                .lock()
+                .unwrap()
+                .completed_locales
+                .contains("fr-FR")
+        );
+        assert_eq!(api.request_count(), 0);
+
+        // a resumed run applies checkpointed keys and only requests the rest
+        let run = test_run(&dir, "", &api);
+        run.checkpoint
+            .lock()
+            .unwrap()
+            .record("fr-FR", "bye", "Au revoir");
+        let outcome = run.run_locale("fr-FR".into()).await.unwrap();
+        assert_eq!((outcome.resumed, outcome.translated), (1, 1));
+        assert_eq!(api.request_count(), 1);
+        assert_eq!(
+            read_json(dir.join("fr-FR.json")),
+            serde_json::json!({"bye": "Au revoir", "hello": "Bonjour"})
+        );
+        assert!(
+            run.checkpoint
+                .lock()
+                .unwrap()
+                .completed_locales
+                .contains("fr-FR")
+        );
+        assert!(
+            !run.state
+                .lock()
+                .unwrap()
+                .is_stale("fr-FR", "bye", "Goodbye")
+        );
+    }
+
+    #[tokio::test]
+    async fn verbatim_keys_are_copied_and_protected_terms_enforced() {
+        let dir = temp_dir("verbatim");
+        fs::write(
+            dir.join("en-GB.json"),
+            r#"{"brand": {"name": "Acme Pro"}, "cta": "Buy Acme now", "legal": "Terms"}"#,
+        )
+        .unwrap();
+        let api = MockApi::start(|body| match body["messages"][1]["content"].as_str() {
+            Some("Buy Acme now") => translation_reply("Achetez maintenant"),
+            _ => translation_reply("Conditions"),
+        });
+        let run = test_run(
+            &dir,
+            "[translate]\nverbatim = [\"brand.**\"]\nprotected_terms = [\"Acme\"]\n",
+            &api,
+        );
+        let outcome = run.run_locale("fr-FR".into()).await.unwrap();
+        assert_eq!((outcome.copied, outcome.translated), (1, 2));
+        let requests = api.requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 2);
+        assert!(
+            requests
+                .iter()
+                .all(|r| r["messages"][1]["content"] != "Acme Pro")
+        );
+        let cta = requests
+            .iter()
+            .find(|r| r["messages"][1]["content"] == "Buy Acme now")
+            .unwrap();
+        assert!(
+            cta["messages"][0]["content"]
+                .as_str()
+                .unwrap()
+                .contains("must appear verbatim): Acme\n")
+        );
+        assert_eq!(outcome.placeholder_issues.len(), 1);
+        assert_eq!(outcome.placeholder_issues[0].key, "cta");
+        assert_eq!(outcome.placeholder_issues[0].missing, ["Acme"]);
+        assert_eq!(
+            read_json(dir.join("fr-FR.json"))["brand"]["name"],
+            "Acme Pro"
+        );
+        // recorded against the source text it was copied from
+        let state = run.state.lock().unwrap();
+        assert!(
+            !state.is_stale("fr-FR", "brand.name", "Acme Pro")
+                && state.is_stale("fr-FR", "brand.name", "Acme Max")
+        );
+    }
+
+    #[tokio::test]
+    async fn examples_leave_out_keys_being_retranslated_and_stand_ins() {
+        let dir = temp_dir("examples");
+        fs::write(
+            dir.join("en-GB.json"),
+            r#"{"cart": {"title": "Your basket", "count": "Items in your basket",
+                "empty": "Your basket is empty", "pay": "Pay for your basket"}}"#,
+        )
+        .unwrap();
+        // title and count were translated from older source texts, pay holds a failure stand-in
+        fs::write(
+            dir.join("fr-FR.json"),
+            r#"{"cart": {"title": "Votre panier", "count": "Articles",
+                "empty": "Votre panier est vide", "pay": "Pay"}}"#,
+        )
+        .unwrap();
+        let api = MockApi::start(|_| translation_reply("x"));
+        let mut run = test_run(&dir, "[translate]\nexamples = 5\n", &api);
+        run.changed_keys = Some(["cart.title".to_string(), "cart.count".to_string()].into());
+        run.state
+            .lock()
+            .unwrap()
+            .record_untranslated("fr-FR", "cart.pay", "Pay");
+
+        let outcome = run.run_locale("fr-FR".into()).await.unwrap();
+        assert_eq!(outcome.translated, 2);
+        let requests = api.requests.lock().unwrap();
+        let system = |i: usize| {
+            requests[i]["messages"][0]["content"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+        for prompt in [system(0), system(1)] {
+            assert!(prompt.contains(r#""Your basket is empty" -> "Votre panier est vide""#));
+            assert!(!prompt.contains(r#""Your basket" ->"#) && !prompt.contains("Articles"));
+            assert!(!prompt.contains(r#"-> "Pay""#), "{}", prompt);
+        }
+    }
+
+    #[tokio::test]
+    async fn fallback_locales_copy_or_adapt_from_their_parent() {
+        let dir = temp_dir("fallback");
+        fs::write(
+            dir.join("en-GB.json"),
+            r#"{"greet": "Hello", "bus": "Bus", "team": "Team"}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("pt-PT.json"),
+            r#"{"greet": "Olá", "bus": "Autocarro", "team": "Equipa"}"#,
+        )
+        .unwrap();
+        // adaptations: regional words change, anything else comes back as given
+        let api = MockApi::start(|body| {
+            match body["messages"][1]["content"].as_str().unwrap_or_default() {
+                "Autocarro" => translation_reply("Ônibus"),
+                "Equipa" => translation_reply("Equipe"),
+                text => translation_reply(text),
+            }
+        });
+        let locales = "[locales.\"pt-PT\"]\n[locales.\"pt-AO\"]\nfallback = \"pt-PT\"\n\
+            [locales.\"pt-BR\"]\nfallback = \"pt-PT\"\n\
+            fallback_mode = \"adapt\"\ndiff_only = true\n";
+
+        let run = test_run(&dir, locales, &api);
+        let copied = run.run_locale("pt-AO".into()).await.unwrap();
+        assert_eq!(
+            (copied.inherited, copied.translated, api.request_count()),
+            (3, 0, 0)
+        );
+        assert_eq!(
+            read_json(dir.join("pt-AO.json")),
+            read_json(dir.join("pt-PT.json"))
+        );
+
+        let adapted = run.run_locale("pt-BR".into()).await.unwrap();
+        assert_eq!(
+            (adapted.inherited, adapted.translated, api.request_count()),
+            (1, 2, 3)
+        );
+        assert_eq!(
+            read_json(dir.join("pt-BR.json")),
+            serde_json::json!({"bus": "Ônibus", "team": "Equipe"})
+        );
+        run.state
+            .lock()
+            .unwrap()
+            .save(&dir.join("state.json"))
+            .unwrap();
+
+        // the unchanged key is remembered instead of being adapted again on every run
+        let run = test_run(&dir, locales, &api);
+        let again = run.run_locale("pt-BR".into()).await.unwrap();
+        assert_eq!(
+            (again.inherited, again.translated, api.request_count()),
+            (1, 0, 3)
+        );
+
+        // until the parent's text changes
+        fs::write(
+            dir.join("pt-PT.json"),
+            r#"{"greet": "Olá!", "bus": "Autocarro", "team": "Equipa"}"#,
+        )
+        .unwrap();
+        let run = test_run(&dir, locales, &api);
+        let changed = run.run_locale("pt-BR".into()).await.unwrap();
+        assert_eq!((changed.inherited, api.request_count()), (1, 4));
+    }
+
+    #[test]
+    fn fallback_parents_run_in_earlier_waves() {
+        let locales: Locales = toml::from_str::<Config>(
+            r#"
+            [locales."fr-CA"]
+            fallback = "fr-FR"
+            [locales."fr-FR"]
+            [locales."pt-BR"]
+            fallback = "pt-PT"
+            "#,
+        )
+        .unwrap()
+        .locales;
+        let waves = fallback_waves(locales.names(), &locales);
+        assert_eq!(
+            waves,
+            [
+                vec!["fr-FR".to_string(), "pt-BR".to_string()],
+                vec!["fr-CA".to_string()]
+            ]
+        );
+    }
+}